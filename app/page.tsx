@@ -18,11 +18,12 @@ import {
   AnchorWallet,
   fetchMarkets,
   placeOrderOnChain,
+  quoteOrder,
   createMarket,
   regionIdFromName,
   hashManifest,
 } from "../lib/hyperlocalClient";
-import { submitLocationProof } from "../lib/zkLocationClient";
+import { submitLocationProof, walletInput } from "../lib/zkLocationClient";
 
 // Devnet USDC mint address
 const USDC_MINT_DEVNET = new PublicKey("Gh9ZwEmdLJ8DscKNTkTqPbNwLNNBjuSzaG9Vp2KGtKJr");
@@ -557,6 +558,12 @@ export default function Page() {
     try {
       console.log("Generating ZK proof for region:", detectedRegion);
       
+      if (!wallet.publicKey) {
+        alert("Please connect your wallet first");
+        setIsProvingLocation(false);
+        return;
+      }
+
       const salt = Math.floor(Math.random() * 1_000_000);
       const proofInput = {
         userLat: detectedRegion.userLat,
@@ -566,17 +573,13 @@ export default function Page() {
         minLon: detectedRegion.minLon,
         maxLon: detectedRegion.maxLon,
         salt,
+        // Binds the proof to this wallet; nobody else can submit it.
+        wallet: walletInput(wallet.publicKey),
       };
       console.log("Proof input:", proofInput);
       
       const proof = await proveLocation(proofInput);
       console.log("ZK Proof generated successfully:", proof);
-
-      if (!wallet.publicKey) {
-        alert("Please connect your wallet first");
-        setIsProvingLocation(false);
-        return;
-      }
       
       console.log("Submitting proof to blockchain...");
       const submitRes = await submitLocationProof(
@@ -629,10 +632,15 @@ export default function Page() {
       // Convert amount to USDC base units (6 decimals)
       const amountBaseUnits = Math.floor(amount * 1_000_000);
       
-      // Calculate minimum shares out with slippage
-      const price = side === "yes" ? selectedMarket.yesPrice : selectedMarket.noPrice;
-      const expectedShares = amount / price;
-      const minSharesOut = Math.floor(expectedShares * (1 - slippageBps / 10000) * 1_000_000);
+      // Minimum shares out with slippage, from an on-chain quote of this exact order
+      const quote = await quoteOrder({
+        connection,
+        wallet: wallet as AnchorWallet,
+        market: new PublicKey(selectedMarket.id),
+        amount: amountBaseUnits,
+        side,
+      });
+      const minSharesOut = Math.floor(quote.sharesOut * (1 - slippageBps / 10000));
 
       const userLocationPubkey = new PublicKey(userStatePda);
 
@@ -667,7 +675,7 @@ export default function Page() {
       await fetchUsdcBalance();
       
       // Update user positions locally (will be refreshed from chain in future)
-      const sharesReceived = amount / price;
+      const sharesReceived = quote.sharesOut / 1_000_000;
       setUserPositions(prev => ({
         ...prev,
         [selectedMarket.id]: {
@@ -1100,7 +1108,8 @@ export default function Page() {
                           }`}>
                             {("open" in (m.status as any) && "Open") ||
                               ("disputed" in (m.status as any) && "Disputed") ||
-                              ("resolved" in (m.status as any) && "Resolved")}
+                              ("proposed" in (m.status as any) && "Proposed") ||
+                              (("finalized" in (m.status as any) || "resolved" in (m.status as any)) && "Resolved")}
                           </span>
                         </div>
                         <div className="text-xs text-slate-500">
@@ -16,6 +16,14 @@ export interface ResolutionManifest {
     // Optional comma-separated list of domains to prioritize
     required_domains?: string;
   };
+
+  // Scalar markets: the numeric value the agent should report, in `unit`.
+  // Bounds must match the market's on-chain lower_bound/upper_bound.
+  scalar?: {
+    unit: string;
+    lower_bound: number;
+    upper_bound: number;
+  };
 }
 
 // Compact manifest format (for on-chain storage to fit tx limits)
@@ -28,10 +36,12 @@ export interface CompactManifest {
 // --- Helper Types for the Agent ---
 
 export interface OracleResult {
-  outcome: "YES" | "NO" | "UNSURE";
+  outcome: "YES" | "NO" | "INVALID" | "UNSURE";
   confidence: number; // 0.0 to 1.0
   reason: string;
   evidenceUrl?: string;
+  // Content the verdict relied on; hashed into the on-chain EvidenceRecord.
+  evidenceContent?: string;
 }
 
 // Basic guard to validate a loaded manifest shape.
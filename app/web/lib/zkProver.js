@@ -6,10 +6,26 @@ function toBigInt(v) {
   return BigInt(v);
 }
 
+// Offset micro-degrees (circuits/coords.circom): shifting by 90° / 180° keeps
+// southern and western coordinates non-negative, which the circuits' range
+// checks and the verifier's bound comparisons rely on.
+const LAT_OFFSET = 90n * SCALE;
+const LON_OFFSET = 180n * SCALE;
+
 function scaleCoord(deg) {
   return toBigInt(Math.round(deg * Number(SCALE)));
 }
 
+export function scaleLat(deg) {
+  if (!(deg >= -90 && deg <= 90)) throw new Error(`Latitude out of range: ${deg}`);
+  return scaleCoord(deg) + LAT_OFFSET;
+}
+
+export function scaleLon(deg) {
+  if (!(deg >= -180 && deg <= 180)) throw new Error(`Longitude out of range: ${deg}`);
+  return scaleCoord(deg) + LON_OFFSET;
+}
+
 function toBytes32(fieldStr) {
   console.log("toBytes32 called with:", fieldStr, "typeof:", typeof fieldStr);
 
@@ -56,7 +72,7 @@ function packPublicSignals(publicSignals) {
 }
 
 
-export async function proveLocation({ userLat, userLon, minLat, maxLat, minLon, maxLon, salt }) {
+export async function proveLocation({ userLat, userLon, minLat, maxLat, minLon, maxLon, salt, wallet }) {
   if (typeof window === "undefined" || !window.snarkjs) {
     throw new Error("snarkjs not loaded");
   }
@@ -65,13 +81,14 @@ export async function proveLocation({ userLat, userLon, minLat, maxLat, minLon,
   const zkey = `/zk/spatial_check_final.zkey`;
 
   const input = {
-    userLat: scaleCoord(userLat).toString(),
-    userLon: scaleCoord(userLon).toString(),
-    minLat: scaleCoord(minLat).toString(),
-    maxLat: scaleCoord(maxLat).toString(),
-    minLon: scaleCoord(minLon).toString(),
-    maxLon: scaleCoord(maxLon).toString(),
+    userLat: scaleLat(userLat).toString(),
+    userLon: scaleLon(userLon).toString(),
+    minLat: scaleLat(minLat).toString(),
+    maxLat: scaleLat(maxLat).toString(),
+    minLon: scaleLon(minLon).toString(),
+    maxLon: scaleLon(maxLon).toString(),
     salt: toBigInt(salt ?? 0).toString(),
+    wallet: toBigInt(wallet ?? 0).toString(),
   };
 
   console.log("Generating proof with input:", input);
@@ -0,0 +1,37 @@
+pragma circom 2.1.4;
+
+include "node_modules/circomlib/circuits/bitify.circom";
+include "node_modules/circomlib/circuits/comparators.circom";
+
+// Coordinates enter every circuit as offset micro-degrees:
+//   lat = round(degrees * 1e6) + 90e6   in [0, 180e6]
+//   lon = round(degrees * 1e6) + 180e6  in [0, 360e6]
+// so the southern and western hemispheres are small non-negative integers
+// rather than p - x. Mirrored by scaleLat / scaleLon in lib/zkProver.js and
+// zk_location_verifier::{lat_input, lon_input}.
+
+// Constrain `in` to [0, max] with max < 2^32, so differences and products of
+// coordinates stay far from the field size.
+template CoordRange(max) {
+    signal input in;
+
+    component bits = Num2Bits(32);
+    bits.in <== in;
+
+    component upper = LessEqThan(32);
+    upper.in[0] <== in;
+    upper.in[1] <== max;
+    upper.out === 1;
+}
+
+template LatInput() {
+    signal input in;
+    component range = CoordRange(180000000);
+    range.in <== in;
+}
+
+template LonInput() {
+    signal input in;
+    component range = CoordRange(360000000);
+    range.in <== in;
+}
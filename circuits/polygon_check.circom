@@ -0,0 +1,115 @@
+pragma circom 2.1.4;
+
+include "node_modules/circomlib/circuits/bitify.circom";
+include "node_modules/circomlib/circuits/comparators.circom";
+include "node_modules/circomlib/circuits/poseidon.circom";
+include "coords.circom";
+
+// Point-in-polygon via ray casting over a fixed number of vertices.
+// Polygons with fewer vertices are padded by repeating vertex 0; padded edges
+// are degenerate and never count as crossings.
+template PolygonCheck(N) {
+    // Public input: Poseidon chain over the (padded, offset-encoded) vertex list,
+    // acc_0 = 0, acc_{i+1} = Poseidon(acc_i, lat_i, lon_i).
+    signal input polygonCommitment;
+    // Public input: the submitting wallet, as in spatial_check.
+    signal input wallet;
+
+    // Private inputs (offset micro-degrees, see coords.circom)
+    signal input vertexLat[N];
+    signal input vertexLon[N];
+    signal input userLat;
+    signal input userLon;
+    signal input salt;
+
+    // Range-check every offset coordinate so the products below stay far from
+    // the field size.
+    component userLatRange = LatInput();
+    userLatRange.in <== userLat;
+    component userLonRange = LonInput();
+    userLonRange.in <== userLon;
+    component latRange[N];
+    component lonRange[N];
+    for (var i = 0; i < N; i++) {
+        latRange[i] = LatInput();
+        latRange[i].in <== vertexLat[i];
+        lonRange[i] = LonInput();
+        lonRange[i].in <== vertexLon[i];
+    }
+
+    // Bind the private vertices to the public commitment.
+    component chain[N];
+    for (var i = 0; i < N; i++) {
+        chain[i] = Poseidon(3);
+        if (i == 0) {
+            chain[i].inputs[0] <== 0;
+        } else {
+            chain[i].inputs[0] <== chain[i - 1].out;
+        }
+        chain[i].inputs[1] <== vertexLat[i];
+        chain[i].inputs[2] <== vertexLon[i];
+    }
+    chain[N - 1].out === polygonCommitment;
+
+    // Cast a ray towards +lon and count edge crossings.
+    var OFFSET = 1 << 68;
+    component aboveI[N];
+    component aboveJ[N];
+    component rising[N];
+    component lhsLess[N];
+    component rhsLess[N];
+    signal straddle[N];
+    signal lhs[N];
+    signal rhs[N];
+    signal pick[N];
+    signal crossing[N];
+    var crossings = 0;
+    for (var i = 0; i < N; i++) {
+        var j = (i + 1) % N;
+
+        aboveI[i] = GreaterThan(32);
+        aboveI[i].in[0] <== vertexLat[i];
+        aboveI[i].in[1] <== userLat;
+        aboveJ[i] = GreaterThan(32);
+        aboveJ[i].in[0] <== vertexLat[j];
+        aboveJ[i].in[1] <== userLat;
+        // Edge spans the ray's latitude (XOR of the two sides).
+        straddle[i] <== aboveI[i].out + aboveJ[i].out - 2 * aboveI[i].out * aboveJ[i].out;
+
+        // userLon < intersection  <=>  lhs < rhs when the edge rises, lhs > rhs otherwise.
+        lhs[i] <== (userLon - vertexLon[i]) * (vertexLat[j] - vertexLat[i]);
+        rhs[i] <== (vertexLon[j] - vertexLon[i]) * (userLat - vertexLat[i]);
+        rising[i] = GreaterThan(32);
+        rising[i].in[0] <== vertexLat[j];
+        rising[i].in[1] <== vertexLat[i];
+        lhsLess[i] = LessThan(70);
+        lhsLess[i].in[0] <== lhs[i] + OFFSET;
+        lhsLess[i].in[1] <== rhs[i] + OFFSET;
+        rhsLess[i] = LessThan(70);
+        rhsLess[i].in[0] <== rhs[i] + OFFSET;
+        rhsLess[i].in[1] <== lhs[i] + OFFSET;
+        pick[i] <== rhsLess[i].out + rising[i].out * (lhsLess[i].out - rhsLess[i].out);
+
+        crossing[i] <== straddle[i] * pick[i];
+        crossings += crossing[i];
+    }
+
+    // Inside iff the crossing count is odd.
+    component parity = Num2Bits(8);
+    parity.in <== crossings;
+    parity.out[0] === 1;
+
+    signal walletSquare;
+    walletSquare <== wallet * wallet;
+
+    // Same nullifier as the other location circuits.
+    component hash = Poseidon(3);
+    hash.inputs[0] <== userLat;
+    hash.inputs[1] <== userLon;
+    hash.inputs[2] <== salt;
+
+    signal output locationNullifier;
+    locationNullifier <== hash.out;
+}
+
+component main {public [polygonCommitment, wallet]} = PolygonCheck(16);
@@ -0,0 +1,59 @@
+pragma circom 2.1.4;
+
+include "node_modules/circomlib/circuits/comparators.circom";
+include "node_modules/circomlib/circuits/poseidon.circom";
+include "coords.circom";
+
+template RadiusCheck() {
+    // Public inputs (circle: centre in offset micro-degrees, see coords.circom,
+    // and squared radius in squared micro-degrees)
+    signal input targetLat;
+    signal input targetLon;
+    signal input radiusSq;
+    // Public input: the submitting wallet, as in spatial_check.
+    signal input wallet;
+
+    // Private inputs
+    signal input userLat;
+    signal input userLon;
+    signal input salt;
+
+    // Range-check the offset coordinates so the squared distance cannot wrap the field.
+    component userLatRange = LatInput();
+    userLatRange.in <== userLat;
+    component userLonRange = LonInput();
+    userLonRange.in <== userLon;
+    component targetLatRange = LatInput();
+    targetLatRange.in <== targetLat;
+    component targetLonRange = LonInput();
+    targetLonRange.in <== targetLon;
+
+    signal dLat;
+    signal dLon;
+    signal dLatSq;
+    signal dLonSq;
+    dLat <== userLat - targetLat;
+    dLon <== userLon - targetLon;
+    dLatSq <== dLat * dLat;
+    dLonSq <== dLon * dLon;
+
+    // distSq <= radiusSq
+    component inside = LessEqThan(96);
+    inside.in[0] <== dLatSq + dLonSq;
+    inside.in[1] <== radiusSq;
+    inside.out === 1;
+
+    signal walletSquare;
+    walletSquare <== wallet * wallet;
+
+    // Same nullifier as spatial_check, so both shapes share one nullifier registry.
+    component hash = Poseidon(3);
+    hash.inputs[0] <== userLat;
+    hash.inputs[1] <== userLon;
+    hash.inputs[2] <== salt;
+
+    signal output locationNullifier;
+    locationNullifier <== hash.out;
+}
+
+component main {public [targetLat, targetLon, radiusSq, wallet]} = RadiusCheck();
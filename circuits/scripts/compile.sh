@@ -1,8 +1,20 @@
 #!/usr/bin/env bash
 set -euo pipefail
 
-CIRCUIT="spatial_check"
-PTAU="powersOfTau28_hez_final_12.ptau"
+# Usage: scripts/compile.sh [spatial_check|radius_check|polygon_check]
+CIRCUIT="${1:-spatial_check}"
+if [ "$CIRCUIT" = "spatial_check" ]; then
+  VK_OUT="verification_key.json"
+else
+  VK_OUT="${CIRCUIT}_verification_key.json"
+fi
+# The ptau must cover the circuit's constraint count: the bbox and radius
+# checks fit in 2^12, PolygonCheck(16) needs ~10k constraints so it takes 2^14.
+if [ "$CIRCUIT" = "polygon_check" ]; then
+  PTAU="powersOfTau28_hez_final_14.ptau"
+else
+  PTAU="powersOfTau28_hez_final_12.ptau"
+fi
 
 if [ ! -f "$PTAU" ]; then
   echo "[compile] Missing $PTAU. Run scripts/setup.sh first." >&2
@@ -19,6 +31,6 @@ echo "[compile] Contributing beacon..."
 npx snarkjs zkey beacon "${CIRCUIT}_0000.zkey" "${CIRCUIT}_final.zkey" 0102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f 10
 
 echo "[compile] Exporting verification key..."
-npx snarkjs zkey export verificationkey "${CIRCUIT}_final.zkey" verification_key.json
+npx snarkjs zkey export verificationkey "${CIRCUIT}_final.zkey" "$VK_OUT"
 
-echo "[compile] Done. Artifacts: ${CIRCUIT}.r1cs, ${CIRCUIT}.wasm, ${CIRCUIT}_final.zkey, verification_key.json"
+echo "[compile] Done. Artifacts: ${CIRCUIT}.r1cs, ${CIRCUIT}.wasm, ${CIRCUIT}_final.zkey, $VK_OUT"
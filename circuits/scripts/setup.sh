@@ -1,16 +1,17 @@
 #!/usr/bin/env bash
 set -euo pipefail
 
-# Download a small Powers of Tau file for Groth16. You can swap in a larger ptau if needed.
-# If network access is restricted, place the file manually at powersOfTau28_hez_final_12.ptau.
+# Download the Powers of Tau files for Groth16: 2^12 covers the bbox and radius
+# circuits, 2^14 covers polygon_check (see compile.sh).
+# If network access is restricted, place the files manually in this directory.
 
-PTAU="powersOfTau28_hez_final_12.ptau"
-
-if [ -f "$PTAU" ]; then
-  echo "[setup] Found $PTAU; skipping download."
-else
-  echo "[setup] Downloading $PTAU..."
-  curl -L "https://hermez.s3-eu-west-1.amazonaws.com/$PTAU" -o "$PTAU"
-fi
+for PTAU in powersOfTau28_hez_final_12.ptau powersOfTau28_hez_final_14.ptau; do
+  if [ -f "$PTAU" ]; then
+    echo "[setup] Found $PTAU; skipping download."
+  else
+    echo "[setup] Downloading $PTAU..."
+    curl -L "https://hermez.s3-eu-west-1.amazonaws.com/$PTAU" -o "$PTAU"
+  fi
+done
 
 echo "[setup] Done."
@@ -4,12 +4,15 @@ include "node_modules/circomlib/circuits/comparators.circom";
 include "node_modules/circomlib/circuits/poseidon.circom";
 
 template SpatialCheck() {
-    // Public inputs (bounding box)
+    // Public inputs (bounding box, offset micro-degrees as in coords.circom)
     signal input minLat;
     signal input maxLat;
     signal input minLon;
     signal input maxLon;
-    
+    // Public input: the submitting wallet (zk_location_verifier::wallet_input),
+    // so a proof copied from the mempool fails for any other signer.
+    signal input wallet;
+
     // Private inputs
     signal input userLat;
     signal input userLon;
@@ -36,7 +39,12 @@ template SpatialCheck() {
     lonUpper.in[1] <== maxLon;
     lonUpper.out === 1;
 
-    // Create nullifier with Poseidon
+    // Constrain the wallet so it stays bound to the proof.
+    signal walletSquare;
+    walletSquare <== wallet * wallet;
+
+    // Create nullifier with Poseidon. As an output of main it is always a
+    // public signal (listed first), and the verifier binds it to one wallet.
     component hash = Poseidon(3);
     hash.inputs[0] <== userLat;
     hash.inputs[1] <== userLon;
@@ -46,4 +54,4 @@ template SpatialCheck() {
     locationNullifier <== hash.out;
 }
 
-component main {public [minLat, maxLat, minLon, maxLon]} = SpatialCheck();
+component main {public [minLat, maxLat, minLon, maxLon, wallet]} = SpatialCheck();
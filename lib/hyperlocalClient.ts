@@ -1,7 +1,12 @@
 import { Connection, PublicKey, SystemProgram, SYSVAR_RENT_PUBKEY } from "@solana/web3.js";
 import * as anchor from "@coral-xyz/anchor";
 import hyperlocalIdl from "../target/idl/hyperlocal_markets.json";
-import { TOKEN_PROGRAM_ID, ASSOCIATED_TOKEN_PROGRAM_ID } from "@solana/spl-token";
+import {
+  TOKEN_PROGRAM_ID,
+  ASSOCIATED_TOKEN_PROGRAM_ID,
+  createAssociatedTokenAccountIdempotentInstruction,
+  getAssociatedTokenAddressSync,
+} from "@solana/spl-token";
 // @ts-ignore - no types available
 import { keccak_256 } from "js-sha3";
 
@@ -70,8 +75,11 @@ export async function placeOrderOnChain(params: {
   market: PublicKey;
   userLocation: PublicKey;
   amount: number; // in USDC base units (e.g., 1 USDC = 1_000_000)
-  side: "yes" | "no";
+  side: MarketSide;
   minSharesOut?: number;
+  // Region PDAs from the user's verified region up to the market's region,
+  // needed when the user proved a sub-region of the market's region.
+  regionChain?: PublicKey[];
 }) {
   const { connection, wallet, market, userLocation, amount, side } = params;
   const minSharesOut = params.minSharesOut ?? 0;
@@ -95,8 +103,10 @@ export async function placeOrderOnChain(params: {
   });
 
   const vault = new PublicKey(marketAccount.vault);
+  const [protocolFeeVault, creatorFeeVault] = feeVaultPdas(market);
 
-  const sideEnum = side === "yes" ? { yes: {} } : { no: {} };
+  const sideEnum = sideArg(side);
+  const shares = shareAccounts(marketAccount, market, wallet.publicKey, side);
 
   return await program.methods
     .placeOrder(new anchor.BN(amount), sideEnum, new anchor.BN(minSharesOut))
@@ -108,11 +118,213 @@ export async function placeOrderOnChain(params: {
       userPosition: userPositionPda,
       traderUsdc,
       vault,
+      protocolFeeVault,
+      creatorFeeVault,
       tokenProgram: TOKEN_PROGRAM_ID,
+      shareMint: shares?.mint ?? null,
+      traderShares: shares?.account ?? null,
     })
+    .preInstructions(shares ? [shares.createAccountIx] : [])
+    .remainingAccounts(
+      (params.regionChain ?? []).map((pubkey) => ({
+        pubkey,
+        isSigner: false,
+        isWritable: false,
+      }))
+    )
     .rpc();
 }
 
+export async function sellSharesOnChain(params: {
+  connection: Connection;
+  wallet: AnchorWallet;
+  market: PublicKey;
+  shares: number;
+  side: MarketSide;
+  minAmountOut?: number; // in USDC base units
+}) {
+  const { connection, wallet, market, shares, side } = params;
+  const program = getProgram(connection, wallet);
+  const marketAccount = await (program.account as any).market.fetch(market);
+
+  const [userPositionPda] = PublicKey.findProgramAddressSync(
+    [
+      Buffer.from("user-position"),
+      market.toBuffer(),
+      wallet.publicKey.toBuffer(),
+    ],
+    HYPERLOCAL_PROGRAM_ID
+  );
+  const traderUsdc = await anchor.utils.token.associatedAddress({
+    mint: USDC_MINT,
+    owner: wallet.publicKey,
+  });
+  const sideEnum = sideArg(side);
+  const shareTokens = shareAccounts(marketAccount, market, wallet.publicKey, side);
+
+  return await program.methods
+    .sellShares(sideEnum, new anchor.BN(shares), new anchor.BN(params.minAmountOut ?? 0))
+    .accounts({
+      trader: wallet.publicKey,
+      market,
+      userPosition: shareTokens ? null : userPositionPda,
+      traderUsdc,
+      vault: new PublicKey(marketAccount.vault),
+      tokenProgram: TOKEN_PROGRAM_ID,
+      shareMint: shareTokens?.mint ?? null,
+      traderShares: shareTokens?.account ?? null,
+    })
+    .rpc();
+}
+
+// Authoritative quotes: simulated on-chain (set_return_data), so the numbers
+// match what place_order/sell_shares would fill right now. All amounts are
+// USDC base units; probabilities are basis points.
+export type OrderQuote = {
+  sharesOut: number;
+  netAmount: number;
+  protocolFee: number;
+  creatorFee: number;
+  probabilityAfterBps: number;
+};
+
+export async function quoteOrder(params: {
+  connection: Connection;
+  wallet: AnchorWallet;
+  market: PublicKey;
+  amount: number;
+  side: MarketSide;
+}): Promise<OrderQuote> {
+  const program = getProgram(params.connection, params.wallet);
+  const quote = await program.methods
+    .quoteOrder(new anchor.BN(params.amount), sideArg(params.side))
+    .accounts({ market: params.market })
+    .view();
+  return {
+    sharesOut: quote.sharesOut.toNumber(),
+    netAmount: quote.netAmount.toNumber(),
+    protocolFee: quote.protocolFee.toNumber(),
+    creatorFee: quote.creatorFee.toNumber(),
+    probabilityAfterBps: quote.probabilityAfterBps.toNumber(),
+  };
+}
+
+export async function quoteSell(params: {
+  connection: Connection;
+  wallet: AnchorWallet;
+  market: PublicKey;
+  shares: number;
+  side: MarketSide;
+}): Promise<{ amountOut: number; probabilityAfterBps: number }> {
+  const program = getProgram(params.connection, params.wallet);
+  const quote = await program.methods
+    .quoteSell(sideArg(params.side), new anchor.BN(params.shares))
+    .accounts({ market: params.market })
+    .view();
+  return {
+    amountOut: quote.amountOut.toNumber(),
+    probabilityAfterBps: quote.probabilityAfterBps.toNumber(),
+  };
+}
+
+// The cheapest place_order amount (fees included) that mints at least `shares`.
+// `payoutPerShare` is what each share would pay in USDC base units if the side
+// wins once the order fills.
+export async function quoteShares(params: {
+  connection: Connection;
+  wallet: AnchorWallet;
+  market: PublicKey;
+  shares: number;
+  side: MarketSide;
+}): Promise<{ amount: number; sharesOut: number; payoutPerShare: number }> {
+  const program = getProgram(params.connection, params.wallet);
+  const quote = await program.methods
+    .quoteShares(sideArg(params.side), new anchor.BN(params.shares))
+    .accounts({ market: params.market })
+    .view();
+  return {
+    amount: quote.amount.toNumber(),
+    sharesOut: quote.sharesOut.toNumber(),
+    payoutPerShare: Number(quote.payoutPerShare.toString()) / 1e18,
+  };
+}
+
+export async function impliedProbability(params: {
+  connection: Connection;
+  wallet: AnchorWallet;
+  market: PublicKey;
+  side: MarketSide;
+}): Promise<number> {
+  const program = getProgram(params.connection, params.wallet);
+  const bps = await program.methods
+    .impliedProbability(sideArg(params.side))
+    .accounts({ market: params.market })
+    .view();
+  return bps.toNumber();
+}
+
+// "yes"/"no" for binary markets, "long"/"short" for scalar ones, or a
+// zero-based outcome index for categorical ones.
+export type MarketSide = "yes" | "no" | "long" | "short" | number;
+
+function sideArg(side: MarketSide) {
+  if (typeof side === "number") return { outcome: { 0: side } };
+  return { [side]: {} };
+}
+
+// YES/NO share mints of a market that called enable_share_tokens.
+export function shareMintPdas(market: PublicKey): [PublicKey, PublicKey] {
+  const [yesMint] = PublicKey.findProgramAddressSync(
+    [Buffer.from("share-mint"), market.toBuffer(), Buffer.from([0])],
+    HYPERLOCAL_PROGRAM_ID
+  );
+  const [noMint] = PublicKey.findProgramAddressSync(
+    [Buffer.from("share-mint"), market.toBuffer(), Buffer.from([1])],
+    HYPERLOCAL_PROGRAM_ID
+  );
+  return [yesMint, noMint];
+}
+
+// Share mint and the owner's ATA for `side`, or null if the market isn't tokenized.
+function shareAccounts(marketAccount: any, market: PublicKey, owner: PublicKey, side: MarketSide) {
+  const mints = (marketAccount.shareMints ?? []) as PublicKey[];
+  if (!mints.length || mints[0].equals(PublicKey.default)) return null;
+  const mint = mints[side === "no" || side === 1 ? 1 : 0];
+  const account = getAssociatedTokenAddressSync(mint, owner);
+  return {
+    mint,
+    account,
+    createAccountIx: createAssociatedTokenAccountIdempotentInstruction(owner, account, owner, mint),
+  };
+}
+
+// Market-owned token accounts that collect protocol and creator fees.
+export function feeVaultPdas(market: PublicKey): [PublicKey, PublicKey] {
+  const [protocolFeeVault] = PublicKey.findProgramAddressSync(
+    [Buffer.from("protocol-fees"), market.toBuffer()],
+    HYPERLOCAL_PROGRAM_ID
+  );
+  const [creatorFeeVault] = PublicKey.findProgramAddressSync(
+    [Buffer.from("creator-fees"), market.toBuffer()],
+    HYPERLOCAL_PROGRAM_ID
+  );
+  return [protocolFeeVault, creatorFeeVault];
+}
+
+// Committee account for markets resolved by M-of-N vote.
+export function resolverSetPda(market: PublicKey): PublicKey {
+  const [pda] = PublicKey.findProgramAddressSync(
+    [Buffer.from("resolver-set"), market.toBuffer()],
+    HYPERLOCAL_PROGRAM_ID
+  );
+  return pda;
+}
+
+// keccak(outcome || salt) as committed by commit_vote; keep the salt to reveal.
+export function voteCommitment(outcome: number, salt: Uint8Array): number[] {
+  return keccak_256.array(Buffer.concat([Buffer.from([outcome]), Buffer.from(salt)]));
+}
+
 // Helper to compute question hash (must match on-chain keccak)
 export function questionHash(question: string): Uint8Array {
   const hash = keccak_256.array(question);
@@ -134,6 +346,12 @@ export interface CreateMarketParams {
   manifestUrl: string; // URL to resolution manifest
   manifestHash: number[]; // 32-byte hash of manifest content
   resolverPubkey?: PublicKey; // Optional custom resolver, defaults to wallet
+  maxProofAgeSlots?: number; // Reject location proofs older than this; 0 = no limit
+  creatorFeeBps?: number; // Creator fee on each order, in basis points (max 500)
+  outcomeCount?: number; // 2 for YES/NO (default), up to 8 for categorical markets
+  initialLiquidity?: number; // USDC base units the creator seeds the pool with; 0 = none
+  initialProbabilityBps?: number; // Starting YES probability when seeding (categorical: 0)
+  lmsrB?: number; // Price with an LMSR of this liquidity (USDC base units) instead of the DPM
 }
 
 export async function createMarket(params: CreateMarketParams): Promise<{
@@ -165,28 +383,65 @@ export async function createMarket(params: CreateMarketParams): Promise<{
     owner: marketPda,
   });
   
+  const [protocolConfigPda] = PublicKey.findProgramAddressSync(
+    [Buffer.from("protocol-config")],
+    HYPERLOCAL_PROGRAM_ID
+  );
+  const [disputeConfigPda] = PublicKey.findProgramAddressSync(
+    [Buffer.from("dispute-config")],
+    HYPERLOCAL_PROGRAM_ID
+  );
+  const [protocolFeeVault, creatorFeeVault] = feeVaultPdas(marketPda);
+  const initialLiquidity = params.initialLiquidity ?? 0;
+  const seeded = initialLiquidity > 0;
+  const lmsr = (params.lmsrB ?? 0) > 0;
+  const [creatorPosition] = PublicKey.findProgramAddressSync(
+    [Buffer.from("user-position"), marketPda.toBuffer(), wallet.publicKey.toBuffer()],
+    HYPERLOCAL_PROGRAM_ID
+  );
+
   // Convert arrays to the format Anchor expects
   const regionIdArray = Array.from(regionId);
   const manifestHashArray = Array.from(manifestHash);
   
-  const txSignature = await program.methods
-    .createMarket(
-      regionIdArray,
-      question,
-      new anchor.BN(closeTime),
-      manifestUrl,
-      manifestHashArray
-    )
+  const commonArgs = [
+    regionIdArray,
+    question,
+    new anchor.BN(closeTime),
+    manifestUrl,
+    manifestHashArray,
+    new anchor.BN(params.maxProofAgeSlots ?? 0),
+    params.creatorFeeBps ?? 0,
+    params.outcomeCount ?? 2,
+  ] as const;
+  // LMSR markets escrow the b·ln(n) subsidy from the creator's USDC account.
+  const method = lmsr
+    ? program.methods.createLmsrMarket(...commonArgs, new anchor.BN(params.lmsrB!))
+    : program.methods.createMarket(
+        ...commonArgs,
+        new anchor.BN(initialLiquidity),
+        params.initialProbabilityBps ?? 0
+      );
+
+  const txSignature = await method
     .accounts({
       payer: wallet.publicKey,
       market: marketPda,
       usdcMint: USDC_MINT,
       vault: vaultAta,
+      protocolConfig: protocolConfigPda,
+      protocolFeeVault,
+      creatorFeeVault,
+      disputeConfig: disputeConfigPda,
       resolver: resolver,
       systemProgram: SystemProgram.programId,
       tokenProgram: TOKEN_PROGRAM_ID,
       associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
       rent: SYSVAR_RENT_PUBKEY,
+      creatorUsdc: seeded || lmsr
+        ? getAssociatedTokenAddressSync(USDC_MINT, wallet.publicKey)
+        : null,
+      creatorPosition: seeded ? creatorPosition : null,
     })
     .rpc();
   
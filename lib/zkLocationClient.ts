@@ -6,11 +6,56 @@ import {
   Transaction,
   TransactionInstruction,
 } from "@solana/web3.js";
+// @ts-ignore - no types available
+import { keccak_256 } from "js-sha3";
 
 export const ZK_LOCATION_PROGRAM_ID = new PublicKey(
   "56qEvUYQnhfEf557ftLVdjtwqfJA6TpG2cnru3MyhT7Z"
 );
 
+// Mirrors zk_location_verifier::region_id; keep the domain tag in sync.
+const REGION_DOMAIN_BBOX = Buffer.from("radius:region:bbox:v1");
+
+// Canonical region id for a bounding box, from the packed big-endian limbs.
+export function regionIdFromBounds(publicInputsPacked: any): Uint8Array {
+  const preimage = Buffer.concat([
+    REGION_DOMAIN_BBOX,
+    Buffer.from(publicInputsPacked.min_lat),
+    Buffer.from(publicInputsPacked.max_lat),
+    Buffer.from(publicInputsPacked.min_lon),
+    Buffer.from(publicInputsPacked.max_lon),
+  ]);
+  return new Uint8Array(keccak_256.array(preimage));
+}
+
+const REGION_DOMAIN_POLYGON = Buffer.from("radius:region:polygon:v1");
+
+// Region id for a polygon geofence, from its 32-byte vertex commitment (taken over
+// scaleLat / scaleLon vertices, as the circuit expects).
+export function polygonRegionId(commitment: Uint8Array): Uint8Array {
+  const preimage = Buffer.concat([REGION_DOMAIN_POLYGON, Buffer.from(commitment)]);
+  return new Uint8Array(keccak_256.array(preimage));
+}
+
+const WALLET_DOMAIN = Buffer.from("radius:wallet:v1");
+
+// Mirrors zk_location_verifier::wallet_input: the proof's wallet public input, as a
+// decimal field element (keccak with the top byte cleared to fit BN254).
+export function walletInput(wallet: PublicKey): string {
+  const digest = keccak_256.array(Buffer.concat([WALLET_DOMAIN, wallet.toBuffer()]));
+  digest[0] = 0;
+  return BigInt("0x" + Buffer.from(digest).toString("hex")).toString();
+}
+
+// Per-(user, region) location state PDA written by submit_location_proof.
+export function userLocationPda(user: PublicKey, regionId: Uint8Array): PublicKey {
+  const [pda] = PublicKey.findProgramAddressSync(
+    [Buffer.from("user-state"), user.toBuffer(), Buffer.from(regionId)],
+    ZK_LOCATION_PROGRAM_ID
+  );
+  return pda;
+}
+
 // From IDL: discriminator for submit_location_proof
 const SUBMIT_LOCATION_PROOF_DISCRIMINATOR = new Uint8Array([
   146, 106, 119, 160, 143, 248, 72, 122,
@@ -52,6 +97,7 @@ function encodePublicInputs(publicInputsPacked: any): number[] {
     for (const b of arr) out.push(b);
   };
 
+  pushBytes32(publicInputsPacked.location_nullifier);
   pushBytes32(publicInputsPacked.min_lat);
   pushBytes32(publicInputsPacked.max_lat);
   pushBytes32(publicInputsPacked.min_lon);
@@ -97,8 +143,17 @@ export async function submitLocationProof(
 
   const connection = getConnection();
 
-  const [userStatePda] = PublicKey.findProgramAddressSync(
-    [Buffer.from("user-state"), wallet.publicKey.toBuffer()],
+  const [configPda] = PublicKey.findProgramAddressSync(
+    [Buffer.from("config")],
+    ZK_LOCATION_PROGRAM_ID
+  );
+  const userStatePda = userLocationPda(
+    wallet.publicKey,
+    regionIdFromBounds(publicInputsPacked)
+  );
+
+  const [nullifierPda] = PublicKey.findProgramAddressSync(
+    [Buffer.from("nullifier"), Buffer.from(publicInputsPacked.location_nullifier)],
     ZK_LOCATION_PROGRAM_ID
   );
 
@@ -111,7 +166,9 @@ export async function submitLocationProof(
 
   const keys = [
     { pubkey: wallet.publicKey, isSigner: true, isWritable: true },
+    { pubkey: configPda, isSigner: false, isWritable: false },
     { pubkey: userStatePda, isSigner: false, isWritable: true },
+    { pubkey: nullifierPda, isSigner: false, isWritable: true },
     { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
   ];
 
@@ -10,10 +10,26 @@ function toBigInt(v) {
   return BigInt(v);
 }
 
+// Offset micro-degrees (circuits/coords.circom): shifting by 90° / 180° keeps
+// southern and western coordinates non-negative, which the circuits' range
+// checks and the verifier's bound comparisons rely on.
+const LAT_OFFSET = 90n * SCALE;
+const LON_OFFSET = 180n * SCALE;
+
 function scaleCoord(deg) {
   return toBigInt(Math.round(deg * Number(SCALE)));
 }
 
+export function scaleLat(deg) {
+  if (!(deg >= -90 && deg <= 90)) throw new Error(`Latitude out of range: ${deg}`);
+  return scaleCoord(deg) + LAT_OFFSET;
+}
+
+export function scaleLon(deg) {
+  if (!(deg >= -180 && deg <= 180)) throw new Error(`Longitude out of range: ${deg}`);
+  return scaleCoord(deg) + LON_OFFSET;
+}
+
 function toBytes32(fieldStr) {
   const v = BigInt(fieldStr);
   let hex = v.toString(16);
@@ -48,18 +64,20 @@ function packProof(proof) {
 function packPublicSignals(publicSignals) {
   console.log("publicSignals:", publicSignals);
 
-  // The circuit has 4 public inputs: minLat, maxLat, minLon, maxLon
-  // The locationNullifier output is NOT public, so publicSignals only contains the 4 inputs
-  if (!Array.isArray(publicSignals) || publicSignals.length < 4) {
-    throw new Error(`Unexpected publicSignals from circuit: got ${publicSignals?.length || 0} signals, expected 4`);
+  // snarkjs lists circuit outputs before public inputs:
+  // [locationNullifier, minLat, maxLat, minLon, maxLon, wallet]
+  // The program derives `wallet` from the signer, so it isn't packed.
+  if (!Array.isArray(publicSignals) || publicSignals.length < 6) {
+    throw new Error(`Unexpected publicSignals from circuit: got ${publicSignals?.length || 0} signals, expected 6`);
   }
 
-  const [minLat, maxLat, minLon, maxLon] = publicSignals;
+  const [locationNullifier, minLat, maxLat, minLon, maxLon] = publicSignals;
 
-  console.log("Parsed signals:", { minLat, maxLat, minLon, maxLon });
+  console.log("Parsed signals:", { locationNullifier, minLat, maxLat, minLon, maxLon });
 
   return {
     // field names must match the Rust struct / IDL exactly
+    location_nullifier: toBytes32(locationNullifier),
     min_lat: toBytes32(minLat),
     max_lat: toBytes32(maxLat),
     min_lon: toBytes32(minLon),
@@ -68,7 +86,9 @@ function packPublicSignals(publicSignals) {
 }
 
 
-export async function proveLocation({ userLat, userLon, minLat, maxLat, minLon, maxLon, salt }) {
+// `wallet` is walletInput(signer) from lib/zkLocationClient.ts; the proof only
+// verifies when that wallet submits it.
+export async function proveLocation({ userLat, userLon, minLat, maxLat, minLon, maxLon, salt, wallet }) {
   if (typeof window === "undefined" || !window.snarkjs) {
     throw new Error("snarkjs not loaded");
   }
@@ -77,13 +97,14 @@ export async function proveLocation({ userLat, userLon, minLat, maxLat, minLon,
   const zkey = `/zk/spatial_check_final.zkey`;
 
   const input = {
-    userLat: scaleCoord(userLat).toString(),
-    userLon: scaleCoord(userLon).toString(),
-    minLat: scaleCoord(minLat).toString(),
-    maxLat: scaleCoord(maxLat).toString(),
-    minLon: scaleCoord(minLon).toString(),
-    maxLon: scaleCoord(maxLon).toString(),
+    userLat: scaleLat(userLat).toString(),
+    userLon: scaleLon(userLon).toString(),
+    minLat: scaleLat(minLat).toString(),
+    maxLat: scaleLat(maxLat).toString(),
+    minLon: scaleLon(minLon).toString(),
+    maxLon: scaleLon(maxLon).toString(),
     salt: toBigInt(salt || 0).toString(),
+    wallet: toBigInt(wallet).toString(),
   };
 
   console.log("Generating proof with input:", input);
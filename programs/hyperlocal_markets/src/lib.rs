@@ -1,7 +1,12 @@
 use anchor_lang::prelude::*;
 use anchor_lang::solana_program::keccak;
+use anchor_lang::system_program;
 use anchor_spl::associated_token::AssociatedToken;
-use anchor_spl::token::{self, Mint, Token, TokenAccount, Transfer};
+use anchor_spl::token::{self, Burn, Mint, MintTo, Token, TokenAccount, Transfer};
+use pricing::{isqrt, pro_rata, Curve, PricingError};
+use zk_location_verifier::{Region, UserLocationState, MAX_REGION_DEPTH};
+
+pub mod pricing;
 
 declare_id!("EA838rrQJPTmk4FNMRV4esgU7rFo5oRLGgW1Nws1jzox");
 
@@ -9,14 +14,94 @@ declare_id!("EA838rrQJPTmk4FNMRV4esgU7rFo5oRLGgW1Nws1jzox");
 pub const ZK_LOCATION_PROGRAM_ID: Pubkey = pubkey!("56qEvUYQnhfEf557ftLVdjtwqfJA6TpG2cnru3MyhT7Z");
 pub const MAX_QUESTION_LEN: usize = 128;
 pub const MAX_URL_LEN: usize = 256;
+pub const MAX_REASON_LEN: usize = 256;
+// Largest manifest a ManifestStore can hold; keeps the account under the
+// 10 KiB limit for accounts created by CPI.
+pub const MAX_ONCHAIN_MANIFEST_LEN: usize = 8_192;
 pub const OUTCOME_NONE: u8 = 0;
 pub const OUTCOME_YES: u8 = 1;
 pub const OUTCOME_NO: u8 = 2;
+// Void resolution: positions are refunded pro rata to their cost basis.
+pub const OUTCOME_INVALID: u8 = 255;
+pub const BPS_DENOMINATOR: u64 = 10_000;
+pub const MAX_PROTOCOL_FEE_BPS: u16 = 500;
+pub const MAX_CREATOR_FEE_BPS: u16 = 500;
+pub const MAX_OUTCOMES: usize = 8;
+pub const MAX_RESOLVERS: usize = 8;
+// Share of a resolver's stake slashed when a challenge overturns its outcome,
+// split evenly between the challenger and the treasury.
+pub const RESOLVER_SLASH_BPS: u16 = 2_000;
+pub const RESOLVER_UNSTAKE_DELAY_SECS: i64 = 7 * 24 * 60 * 60;
+pub const MAX_DISPUTE_WINDOW_SECS: i64 = 30 * 24 * 60 * 60;
+// How long after close a committee without commit-reveal may vote before
+// anyone can tally and escalate a deadlock to the arbiter.
+pub const COMMITTEE_VOTE_WINDOW_SECS: i64 = 3 * 24 * 60 * 60;
 
 #[program]
 pub mod hyperlocal_markets {
     use super::*;
 
+    /// One-time setup, signed by the program's upgrade authority so nobody can
+    /// front-run the deploy and install their own admin and treasury.
+    pub fn init_protocol_config(
+        ctx: Context<InitProtocolConfig>,
+        protocol_fee_bps: u16,
+        treasury: Pubkey,
+    ) -> Result<()> {
+        require!(
+            protocol_fee_bps <= MAX_PROTOCOL_FEE_BPS,
+            MarketError::FeeTooHigh
+        );
+        let config = &mut ctx.accounts.protocol_config;
+        config.admin = ctx.accounts.admin.key();
+        config.treasury = treasury;
+        config.protocol_fee_bps = protocol_fee_bps;
+        config.bump = ctx.bumps.protocol_config;
+        Ok(())
+    }
+
+    /// Applies to markets created afterwards; existing markets keep their snapshot.
+    pub fn update_protocol_config(
+        ctx: Context<UpdateProtocolConfig>,
+        protocol_fee_bps: u16,
+        treasury: Pubkey,
+    ) -> Result<()> {
+        require!(
+            protocol_fee_bps <= MAX_PROTOCOL_FEE_BPS,
+            MarketError::FeeTooHigh
+        );
+        let config = &mut ctx.accounts.protocol_config;
+        config.treasury = treasury;
+        config.protocol_fee_bps = protocol_fee_bps;
+        Ok(())
+    }
+
+    /// Creates or updates the dispute settings new markets snapshot: the arbiter
+    /// who settles challenged agent resolutions, how long a proposal stays open
+    /// to challenge, and the USDC bond a challenger must post.
+    pub fn set_dispute_config(
+        ctx: Context<SetDisputeConfig>,
+        arbiter: Pubkey,
+        dispute_window_secs: i64,
+        challenge_bond: u64,
+    ) -> Result<()> {
+        require!(
+            (0..=MAX_DISPUTE_WINDOW_SECS).contains(&dispute_window_secs),
+            MarketError::InvalidDisputeWindow
+        );
+        let config = &mut ctx.accounts.dispute_config;
+        config.arbiter = arbiter;
+        config.dispute_window_secs = dispute_window_secs;
+        config.challenge_bond = challenge_bond;
+        config.bump = ctx.bumps.dispute_config;
+        Ok(())
+    }
+
+    /// A non-zero `initial_liquidity` is deposited by the creator and minted
+    /// as shares to the creator's `UserPosition`, priced so outcome 0 (YES or
+    /// Long) starts at `initial_probability_bps`; categorical markets start
+    /// uniform and take 0. Without it the market starts from 1-share priors.
+    #[allow(clippy::too_many_arguments)]
     pub fn create_market(
         ctx: Context<CreateMarket>,
         region_id: [u8; 32],
@@ -24,11 +109,28 @@ pub mod hyperlocal_markets {
         close_time: i64,
         manifest_url: String,
         manifest_hash: [u8; 32],
+        max_proof_age_slots: u64,
+        creator_fee_bps: u16,
+        outcome_count: u8,
+        initial_liquidity: u64,
+        initial_probability_bps: u16,
     ) -> Result<()> {
         require!(
-            question.as_bytes().len() <= MAX_QUESTION_LEN,
+            question.len() <= MAX_QUESTION_LEN,
             MarketError::QuestionTooLong
         );
+        require!(
+            manifest_url.len() <= MAX_URL_LEN,
+            MarketError::ManifestUrlTooLong
+        );
+        require!(
+            creator_fee_bps <= MAX_CREATOR_FEE_BPS,
+            MarketError::FeeTooHigh
+        );
+        require!(
+            (2..=MAX_OUTCOMES as u8).contains(&outcome_count),
+            MarketError::InvalidOutcomeCount
+        );
         let qhash = question_hash(&question);
         let market = &mut ctx.accounts.market;
         market.region_id = region_id;
@@ -42,18 +144,162 @@ pub mod hyperlocal_markets {
         market.resolver = ctx.accounts.resolver.key();
         market.market_bump = ctx.bumps.market;
         market.creator = ctx.accounts.payer.key();
-        // Seed tiny priors to avoid division by zero; negligible vs real flow.
-        market.yes_shares = 1;
-        market.no_shares = 1;
-        market.total_pool = 0;
+        market.outcome_count = outcome_count;
+        market.cost_basis_tracked = true;
+        if market.pricing_model == PricingModel::Lmsr {
+            // Shares start at zero; the creator escrows the maker's worst-case loss.
+            require!(initial_liquidity == 0, MarketError::UnsupportedPricingModel);
+            let subsidy = pricing::lmsr_max_loss(market.lmsr_b, market.num_outcomes())?;
+            let creator_usdc = ctx
+                .accounts
+                .creator_usdc
+                .as_ref()
+                .ok_or(MarketError::MissingLiquidityAccounts)?;
+            let cpi_ctx = CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: creator_usdc.to_account_info(),
+                    to: ctx.accounts.vault.to_account_info(),
+                    authority: ctx.accounts.payer.to_account_info(),
+                },
+            );
+            token::transfer(cpi_ctx, subsidy)?;
+            market.total_pool = subsidy;
+        } else if initial_liquidity > 0 {
+            let seeded = seed_shares(
+                initial_liquidity,
+                initial_probability_bps,
+                market.num_outcomes(),
+            )?;
+            let creator_usdc = ctx
+                .accounts
+                .creator_usdc
+                .as_ref()
+                .ok_or(MarketError::MissingLiquidityAccounts)?;
+            let cpi_ctx = CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: creator_usdc.to_account_info(),
+                    to: ctx.accounts.vault.to_account_info(),
+                    authority: ctx.accounts.payer.to_account_info(),
+                },
+            );
+            token::transfer(cpi_ctx, initial_liquidity)?;
+
+            let position = ctx
+                .accounts
+                .creator_position
+                .as_mut()
+                .ok_or(MarketError::MissingLiquidityAccounts)?;
+            position.bump = ctx
+                .bumps
+                .creator_position
+                .ok_or(MarketError::MissingLiquidityAccounts)?;
+            position.cost_basis = initial_liquidity;
+            for (index, shares) in seeded.into_iter().enumerate() {
+                market.set_shares(index, shares);
+                position.set_shares(market, index, shares);
+            }
+            market.total_pool = initial_liquidity;
+            market.total_cost_basis = initial_liquidity;
+        } else {
+            // Seed tiny priors to avoid division by zero; negligible vs real flow.
+            for index in 0..market.num_outcomes() {
+                market.set_shares(index, 1);
+            }
+            market.total_pool = 0;
+        }
         market.manifest_url = manifest_url;
         market.manifest_hash = manifest_hash;
         market.resolved_evidence_url = "".to_string();
         market.status = ResolutionStatus::Open;
         market.agent_outcome = OUTCOME_NONE;
+        market.max_proof_age_slots = max_proof_age_slots;
+        market.protocol_fee_bps = ctx.accounts.protocol_config.protocol_fee_bps;
+        market.creator_fee_bps = creator_fee_bps;
+        market.protocol_fee_vault = ctx.accounts.protocol_fee_vault.key();
+        market.creator_fee_vault = ctx.accounts.creator_fee_vault.key();
+        market.arbiter = ctx.accounts.dispute_config.arbiter;
+        market.dispute_window_secs = ctx.accounts.dispute_config.dispute_window_secs;
+        market.challenge_bond = ctx.accounts.dispute_config.challenge_bond;
         Ok(())
     }
 
+    /// Numeric market over [lower_bound, upper_bound]. Long and short shares trade
+    /// on the same DPM curve as YES/NO; see `resolve_scalar_market` for payout.
+    #[allow(clippy::too_many_arguments)]
+    pub fn create_scalar_market(
+        ctx: Context<CreateMarket>,
+        region_id: [u8; 32],
+        question: String,
+        close_time: i64,
+        manifest_url: String,
+        manifest_hash: [u8; 32],
+        max_proof_age_slots: u64,
+        creator_fee_bps: u16,
+        lower_bound: i64,
+        upper_bound: i64,
+        initial_liquidity: u64,
+        initial_probability_bps: u16,
+    ) -> Result<()> {
+        require!(lower_bound < upper_bound, MarketError::InvalidScalarBounds);
+        // create_market leaves these untouched.
+        let market = &mut ctx.accounts.market;
+        market.kind = MarketKind::Scalar;
+        market.lower_bound = lower_bound;
+        market.upper_bound = upper_bound;
+        create_market(
+            ctx,
+            region_id,
+            question,
+            close_time,
+            manifest_url,
+            manifest_hash,
+            max_proof_age_slots,
+            creator_fee_bps,
+            2,
+            initial_liquidity,
+            initial_probability_bps,
+        )
+    }
+
+    /// Binary or categorical market priced by an LMSR with liquidity `b`
+    /// (USDC base units) instead of the DPM. The creator escrows the subsidy
+    /// b·ln(outcome_count) from `creator_usdc`; each winning share pays one
+    /// base unit and `withdraw_lmsr_surplus` returns what's left over.
+    #[allow(clippy::too_many_arguments)]
+    pub fn create_lmsr_market(
+        ctx: Context<CreateMarket>,
+        region_id: [u8; 32],
+        question: String,
+        close_time: i64,
+        manifest_url: String,
+        manifest_hash: [u8; 32],
+        max_proof_age_slots: u64,
+        creator_fee_bps: u16,
+        outcome_count: u8,
+        liquidity_b: u64,
+    ) -> Result<()> {
+        require!(liquidity_b > 0, MarketError::InvalidLiquidityParameter);
+        // create_market leaves these untouched.
+        let market = &mut ctx.accounts.market;
+        market.pricing_model = PricingModel::Lmsr;
+        market.lmsr_b = liquidity_b;
+        create_market(
+            ctx,
+            region_id,
+            question,
+            close_time,
+            manifest_url,
+            manifest_hash,
+            max_proof_age_slots,
+            creator_fee_bps,
+            outcome_count,
+            0,
+            0,
+        )
+    }
+
     pub fn place_order(
         ctx: Context<PlaceOrder>,
         amount: u64,
@@ -63,17 +309,80 @@ pub mod hyperlocal_markets {
         let market = &ctx.accounts.market;
         let user_location = &ctx.accounts.user_location;
 
+        require!(
+            UserLocationState::is_address_for(
+                &user_location.key(),
+                &ctx.accounts.trader.key(),
+                &user_location.region_id
+            ),
+            MarketError::UserLocationMismatch
+        );
         require!(user_location.is_verified, MarketError::LocationNotVerified);
+        // Zero disables the freshness check (markets created before it existed).
+        if market.max_proof_age_slots > 0 {
+            let age = Clock::get()?
+                .slot
+                .saturating_sub(user_location.last_verified_slot);
+            require!(
+                age <= market.max_proof_age_slots,
+                MarketError::LocationProofStale
+            );
+        }
         require!(
-            user_location.region_id == market.region_id,
+            region_in_scope(
+                &user_location.region_id,
+                &market.region_id,
+                ctx.remaining_accounts
+            )?,
             MarketError::WrongRegion
         );
-        require!(!market.resolved, MarketError::MarketClosed);
         require!(
-            Clock::get()?.unix_timestamp < market.close_time,
+            market.phase(Clock::get()?.unix_timestamp) == ResolutionStatus::Open,
             MarketError::MarketClosed
         );
 
+        // Skim fees; only the net amount enters the pool.
+        let OrderQuote {
+            protocol_fee,
+            creator_fee,
+            net_amount,
+            shares_out,
+            ..
+        } = market.quote_order(amount, side)?;
+        require!(net_amount > 0, MarketError::InvalidAmount);
+        if protocol_fee > 0 {
+            let fee_vault = ctx
+                .accounts
+                .protocol_fee_vault
+                .as_ref()
+                .ok_or(MarketError::MissingFeeVault)?;
+            let cpi_ctx = CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.trader_usdc.to_account_info(),
+                    to: fee_vault.to_account_info(),
+                    authority: ctx.accounts.trader.to_account_info(),
+                },
+            );
+            token::transfer(cpi_ctx, protocol_fee)?;
+        }
+        if creator_fee > 0 {
+            let fee_vault = ctx
+                .accounts
+                .creator_fee_vault
+                .as_ref()
+                .ok_or(MarketError::MissingFeeVault)?;
+            let cpi_ctx = CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.trader_usdc.to_account_info(),
+                    to: fee_vault.to_account_info(),
+                    authority: ctx.accounts.trader.to_account_info(),
+                },
+            );
+            token::transfer(cpi_ctx, creator_fee)?;
+        }
+
         // Transfer USDC into the vault.
         let cpi_ctx = CpiContext::new(
             ctx.accounts.token_program.to_account_info(),
@@ -83,51 +392,19 @@ pub mod hyperlocal_markets {
                 authority: ctx.accounts.trader.to_account_info(),
             },
         );
-        token::transfer(cpi_ctx, amount)?;
+        token::transfer(cpi_ctx, net_amount)?;
 
-        // DPM math.
-        let mut yes = market.yes_shares as u128;
-        let mut no = market.no_shares as u128;
+        let index = market.outcome_index(side)?;
+        let shares = market.all_shares();
         let current_pool = market.total_pool as u128;
-        let amount_u128 = amount as u128;
+        let amount_u128 = net_amount as u128;
         let new_total = current_pool
             .checked_add(amount_u128)
             .ok_or(MarketError::MathOverflow)?;
-
-        let (new_yes, new_no, minted) = match side {
-            Side::Yes => {
-                if no == 0 {
-                    no = 1;
-                }
-                let new_yes_shares = isqrt(
-                    new_total
-                        .checked_mul(new_total)
-                        .ok_or(MarketError::MathOverflow)?
-                        .checked_sub(no.checked_mul(no).ok_or(MarketError::MathOverflow)?)
-                        .ok_or(MarketError::MathOverflow)?,
-                );
-                let minted = new_yes_shares
-                    .checked_sub(yes)
-                    .ok_or(MarketError::MathUnderflow)?;
-                (new_yes_shares, no, minted)
-            }
-            Side::No => {
-                if yes == 0 {
-                    yes = 1;
-                }
-                let new_no_shares = isqrt(
-                    new_total
-                        .checked_mul(new_total)
-                        .ok_or(MarketError::MathOverflow)?
-                        .checked_sub(yes.checked_mul(yes).ok_or(MarketError::MathOverflow)?)
-                        .ok_or(MarketError::MathOverflow)?,
-                );
-                let minted = new_no_shares
-                    .checked_sub(no)
-                    .ok_or(MarketError::MathUnderflow)?;
-                (yes, new_no_shares, minted)
-            }
-        };
+        let minted = shares_out as u128;
+        let new_outcome_shares = shares[index]
+            .checked_add(minted)
+            .ok_or(MarketError::MathOverflow)?;
 
         require!(
             minted >= min_shares_out as u128,
@@ -138,68 +415,328 @@ pub mod hyperlocal_markets {
         let market = &mut ctx.accounts.market;
         market.total_pool =
             u64::try_from(new_total).map_err(|_| MarketError::MathOverflow)?;
-        match side {
-            Side::Yes => market.yes_shares = new_yes as u128,
-            Side::No => market.no_shares = new_no as u128,
-        }
+        market.set_shares(index, new_outcome_shares);
+        // Every buy must land in the stored market, or claims, sells and
+        // refunds would price against a pool that never moved.
+        require!(
+            market.total_pool as u128 > current_pool,
+            MarketError::InvalidAmount
+        );
 
-        // Update user position.
         let user_pos = &mut ctx.accounts.user_position;
         user_pos.bump = ctx.bumps.user_position;
-        match side {
-            Side::Yes => user_pos.yes_shares = user_pos
-                .yes_shares
-                .checked_add(minted)
-                .ok_or(MarketError::MathOverflow)?,
-            Side::No => user_pos.no_shares = user_pos
-                .no_shares
-                .checked_add(minted)
-                .ok_or(MarketError::MathOverflow)?,
+        if market.is_tokenized() {
+            // The share tokens are the position.
+            let (share_mint, trader_shares) = share_accounts(
+                market,
+                index,
+                &ctx.accounts.share_mint,
+                &ctx.accounts.trader_shares,
+            )?;
+            let question_hash = market.question_hash;
+            let seeds: &[&[u8]] = &[
+                b"market",
+                market.creator.as_ref(),
+                question_hash.as_ref(),
+                &[market.market_bump],
+            ];
+            let signer = &[seeds];
+            let cpi_ctx = CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                MintTo {
+                    mint: share_mint.to_account_info(),
+                    to: trader_shares.to_account_info(),
+                    authority: market.to_account_info(),
+                },
+                signer,
+            );
+            token::mint_to(
+                cpi_ctx,
+                u64::try_from(minted).map_err(|_| MarketError::MathOverflow)?,
+            )?;
+        } else {
+            let held = user_pos.shares(market, index);
+            user_pos.set_shares(
+                market,
+                index,
+                held.checked_add(minted).ok_or(MarketError::MathOverflow)?,
+            );
+            user_pos.cost_basis = user_pos
+                .cost_basis
+                .checked_add(net_amount)
+                .ok_or(MarketError::MathOverflow)?;
+            market.total_cost_basis = market
+                .total_cost_basis
+                .checked_add(net_amount)
+                .ok_or(MarketError::MathOverflow)?;
         }
 
         emit!(OrderPlaced {
             trader: ctx.accounts.trader.key(),
             market: market.key(),
             amount,
+            fees: protocol_fee + creator_fee,
+            slot: Clock::get()?.slot,
+        });
+        Ok(())
+    }
+
+    /// Burn shares before close and take USDC back out of the pool. Inverts the
+    /// buy curve: pays the drop in sqrt(sum of squares), rounded so the vault
+    /// never pays out more than the curve allows.
+    pub fn sell_shares(
+        ctx: Context<SellShares>,
+        side: Side,
+        shares: u64,
+        min_amount_out: u64,
+    ) -> Result<()> {
+        let market = &ctx.accounts.market;
+        require!(
+            market.phase(Clock::get()?.unix_timestamp) == ResolutionStatus::Open,
+            MarketError::MarketClosed
+        );
+        require!(shares > 0, MarketError::InvalidAmount);
+
+        let index = market.outcome_index(side)?;
+        let shares = shares as u128;
+        let held = if market.is_tokenized() {
+            share_accounts(
+                market,
+                index,
+                &ctx.accounts.share_mint,
+                &ctx.accounts.trader_shares,
+            )?
+            .1
+            .amount as u128
+        } else {
+            ctx.accounts
+                .user_position
+                .as_ref()
+                .ok_or(MarketError::MissingPosition)?
+                .shares(market, index)
+        };
+        require!(shares <= held, MarketError::InsufficientShares);
+
+        let amount_out = market.quote_sell(side, shares as u64)?.amount_out;
+        let new_total = market
+            .total_pool
+            .checked_sub(amount_out)
+            .ok_or(MarketError::MathUnderflow)?;
+        let remaining = market
+            .shares(index)
+            .checked_sub(shares)
+            .ok_or(MarketError::MathUnderflow)?;
+        require!(amount_out >= min_amount_out, MarketError::SlippageExceeded);
+
+        // Transfer from vault to trader.
+        let question_hash = market.question_hash;
+        let seeds: &[&[u8]] = &[
+            b"market",
+            market.creator.as_ref(),
+            question_hash.as_ref(),
+            &[market.market_bump],
+        ];
+        let signer = &[seeds];
+        let cpi_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.vault.to_account_info(),
+                to: ctx.accounts.trader_usdc.to_account_info(),
+                authority: market.to_account_info(),
+            },
+            signer,
+        );
+        token::transfer(cpi_ctx, amount_out)?;
+
+        // Update market.
+        let market = &mut ctx.accounts.market;
+        market.total_pool = new_total;
+        market.set_shares(index, remaining);
+
+        if market.is_tokenized() {
+            let (share_mint, trader_shares) = share_accounts(
+                market,
+                index,
+                &ctx.accounts.share_mint,
+                &ctx.accounts.trader_shares,
+            )?;
+            let cpi_ctx = CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Burn {
+                    mint: share_mint.to_account_info(),
+                    from: trader_shares.to_account_info(),
+                    authority: ctx.accounts.trader.to_account_info(),
+                },
+            );
+            token::burn(cpi_ctx, shares as u64)?;
+        } else {
+            // Cost basis shrinks with the fraction of shares sold.
+            let user_pos = ctx
+                .accounts
+                .user_position
+                .as_mut()
+                .ok_or(MarketError::MissingPosition)?;
+            user_pos.set_shares(market, index, held - shares);
+            let basis_sold =
+                u64::try_from(pro_rata(shares, user_pos.cost_basis as u128, held)?)
+                    .map_err(|_| MarketError::MathOverflow)?;
+            user_pos.cost_basis -= basis_sold;
+            market.total_cost_basis = market.total_cost_basis.saturating_sub(basis_sold);
+        }
+
+        emit!(SharesSold {
+            trader: ctx.accounts.trader.key(),
+            market: market.key(),
+            shares: shares as u64,
+            amount_out,
             slot: Clock::get()?.slot,
         });
         Ok(())
     }
 
-    pub fn resolve_market(ctx: Context<ResolveMarket>, outcome: Outcome) -> Result<()> {
+    /// What `place_order(amount, side, _)` would mint right now, without moving
+    /// funds. Returned via set_return_data; simulate it to pick `min_shares_out`.
+    pub fn quote_order(ctx: Context<Quote>, amount: u64, side: Side) -> Result<OrderQuote> {
+        let market = &ctx.accounts.market;
+        require!(
+            market.phase(Clock::get()?.unix_timestamp) == ResolutionStatus::Open,
+            MarketError::MarketClosed
+        );
+        market.quote_order(amount, side)
+    }
+
+    /// What `sell_shares(side, shares, _)` would pay right now, without moving
+    /// funds. Returned via set_return_data; simulate it to pick `min_amount_out`.
+    pub fn quote_sell(ctx: Context<Quote>, side: Side, shares: u64) -> Result<SellQuote> {
+        let market = &ctx.accounts.market;
+        require!(
+            market.phase(Clock::get()?.unix_timestamp) == ResolutionStatus::Open,
+            MarketError::MarketClosed
+        );
+        require!(shares > 0, MarketError::InvalidAmount);
+        market.quote_sell(side, shares)
+    }
+
+    /// The smallest `place_order` amount (fees included) that mints at least
+    /// `shares` of `side` right now, without moving funds. Returned via
+    /// set_return_data.
+    pub fn quote_shares(ctx: Context<Quote>, side: Side, shares: u64) -> Result<SharesQuote> {
+        let market = &ctx.accounts.market;
+        require!(
+            market.phase(Clock::get()?.unix_timestamp) == ResolutionStatus::Open,
+            MarketError::MarketClosed
+        );
+        require!(shares > 0, MarketError::InvalidAmount);
+        market.quote_shares(side, shares)
+    }
+
+    /// The market-implied probability of `side` in basis points, returned via
+    /// set_return_data.
+    pub fn implied_probability(ctx: Context<Quote>, side: Side) -> Result<u64> {
+        let market = &ctx.accounts.market;
+        let index = market.outcome_index(side)?;
+        Ok(market
+            .curve()
+            .implied_probability_bps(&market.all_shares(), index)?)
+    }
+
+    /// Every instruction that settles an outcome takes the keccak256 of the
+    /// manifest the caller resolved against; it must equal `manifest_hash`.
+    /// On markets with a dispute window the resolver's outcome is only
+    /// proposed, and settles through `finalize_resolution` like the agent's.
+    pub fn resolve_market(
+        ctx: Context<ResolveMarket>,
+        outcome: Outcome,
+        manifest_hash: [u8; 32],
+    ) -> Result<()> {
         let market = &mut ctx.accounts.market;
-        require!(!market.resolved, MarketError::AlreadyResolved);
         require!(
             ctx.accounts.resolver.key() == market.resolver,
             MarketError::UnauthorizedResolver
         );
-        market.resolved = true;
-        market.outcome = match outcome {
-            Outcome::Yes => 1,
-            Outcome::No => 2,
+        market.require_manifest(&manifest_hash)?;
+        require!(!market.has_committee(), MarketError::CommitteeMarket);
+        market.require_direct_settlement(Clock::get()?.unix_timestamp)?;
+        let outcome = match outcome {
+            Outcome::Yes => OUTCOME_YES,
+            Outcome::No => OUTCOME_NO,
+            Outcome::Index(index) => index.checked_add(1).ok_or(MarketError::InvalidOutcome)?,
+            Outcome::Invalid => OUTCOME_INVALID,
         };
+        require!(market.is_final_outcome(outcome), MarketError::InvalidOutcome);
+        settle_as_resolver(market, outcome, Clock::get()?.unix_timestamp)?;
         Ok(())
     }
 
-    pub fn claim(ctx: Context<Claim>) -> Result<()> {
+    /// Settle a scalar market on a numeric value (resolver authority). Values
+    /// outside the range are clamped to the nearest bound. Like
+    /// `resolve_market`, this only proposes when the market has a dispute window.
+    pub fn resolve_scalar_market(
+        ctx: Context<ResolveMarket>,
+        value: i64,
+        manifest_hash: [u8; 32],
+    ) -> Result<()> {
         let market = &mut ctx.accounts.market;
-        require!(market.resolved, MarketError::NotResolved);
+        require!(
+            ctx.accounts.resolver.key() == market.resolver,
+            MarketError::UnauthorizedResolver
+        );
+        market.require_manifest(&manifest_hash)?;
+        require!(market.kind == MarketKind::Scalar, MarketError::NotScalarMarket);
+        require!(!market.has_committee(), MarketError::CommitteeMarket);
+        let now = Clock::get()?.unix_timestamp;
+        market.require_direct_settlement(now)?;
+        market.resolved_value = value.clamp(market.lower_bound, market.upper_bound);
+        settle_as_resolver(market, OUTCOME_NONE, now)?;
+        Ok(())
+    }
 
-        let user_pos = &mut ctx.accounts.user_position;
-        require!(!user_pos.claimed, MarketError::AlreadyClaimed);
+    /// Tokenized markets pay whoever holds the share tokens: `share_mint` picks
+    /// the side being redeemed and the trader's whole balance is burned.
+    pub fn claim(ctx: Context<Claim>) -> Result<()> {
+        let market = &ctx.accounts.market;
+        require!(
+            market.phase(Clock::get()?.unix_timestamp) == ResolutionStatus::Finalized,
+            MarketError::NotResolved
+        );
 
-        let (winning_total, user_shares) = match market.outcome {
-            1 => (market.yes_shares, user_pos.yes_shares),
-            2 => (market.no_shares, user_pos.no_shares),
-            _ => return err!(MarketError::NotResolved),
-        };
-        require!(winning_total > 0, MarketError::MathUnderflow);
+        let payout_u128 = if market.is_tokenized() {
+            let share_mint = ctx
+                .accounts
+                .share_mint
+                .as_ref()
+                .ok_or(MarketError::MissingShareAccount)?;
+            let index = market.share_index(&share_mint.key())?;
+            let (share_mint, trader_shares) = share_accounts(
+                market,
+                index,
+                &ctx.accounts.share_mint,
+                &ctx.accounts.trader_shares,
+            )?;
+            let balance = trader_shares.amount;
+            require!(balance > 0, MarketError::InsufficientShares);
+            let payout = share_token_payout(market, index, balance as u128)?;
 
-        let payout_u128 = user_shares
-            .checked_mul(market.total_pool as u128)
-            .ok_or(MarketError::MathOverflow)?
-            .checked_div(winning_total)
-            .ok_or(MarketError::MathUnderflow)?;
+            let cpi_ctx = CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Burn {
+                    mint: share_mint.to_account_info(),
+                    from: trader_shares.to_account_info(),
+                    authority: ctx.accounts.trader.to_account_info(),
+                },
+            );
+            token::burn(cpi_ctx, balance)?;
+            payout
+        } else {
+            let user_pos = ctx
+                .accounts
+                .user_position
+                .as_mut()
+                .ok_or(MarketError::MissingPosition)?;
+            require!(!user_pos.claimed, MarketError::AlreadyClaimed);
+            user_pos.claimed = true;
+            position_payout(market, user_pos)?
+        };
 
         let payout = u64::try_from(payout_u128).map_err(|_| MarketError::MathOverflow)?;
 
@@ -211,7 +748,7 @@ pub mod hyperlocal_markets {
             question_hash.as_ref(),
             &[market.market_bump],
         ];
-        let signer = &[&seeds[..]];
+        let signer = &[seeds];
         let cpi_ctx = CpiContext::new_with_signer(
             ctx.accounts.token_program.to_account_info(),
             Transfer {
@@ -222,220 +759,1346 @@ pub mod hyperlocal_markets {
             signer,
         );
         token::transfer(cpi_ctx, payout)?;
-
-        user_pos.claimed = true;
         Ok(())
     }
 
+    /// Each attempt writes its evidence to a new `EvidenceRecord`, so earlier
+    /// attempts stay auditable.
     pub fn agent_attempt_resolution(
         ctx: Context<AgentAttemptResolution>,
         outcome: u8,
-        evidence: String,
+        evidence: Evidence,
         reason: String,
+        manifest_hash: [u8; 32],
     ) -> Result<()> {
+        require!(reason.len() <= MAX_REASON_LEN, MarketError::ReasonTooLong);
         let market = &mut ctx.accounts.market;
+        market.require_manifest(&manifest_hash)?;
+        require!(!market.has_committee(), MarketError::CommitteeMarket);
+        require!(
+            outcome == OUTCOME_NONE || market.is_final_outcome(outcome),
+            MarketError::InvalidOutcome
+        );
+        // A definite answer only becomes final after the dispute window;
+        // OUTCOME_NONE (unsure) parks the market as Disputed.
+        let now = Clock::get()?.unix_timestamp;
+        let from = market.phase(now);
+        let to = if outcome == OUTCOME_NONE {
+            ResolutionStatus::Disputed
+        } else {
+            ResolutionStatus::Proposed
+        };
+        check_transition(from, to)?;
+        require!(from != ResolutionStatus::Proposed, MarketError::ProposalPending);
+        require!(
+            market.challenger == Pubkey::default(),
+            MarketError::ChallengePending
+        );
+        if market.min_resolver_stake > 0 {
+            let stake = ctx
+                .accounts
+                .resolver_stake
+                .as_mut()
+                .ok_or(MarketError::MissingResolverStake)?;
+            stake.require_active(market.min_resolver_stake)?;
+            if to == ResolutionStatus::Proposed {
+                stake.open_proposals = stake
+                    .open_proposals
+                    .checked_add(1)
+                    .ok_or(MarketError::MathOverflow)?;
+                market.stake_locked = true;
+            }
+        }
 
         market.agent_outcome = outcome;
-        market.resolved_evidence_url = evidence.clone();
+        market.status = to;
+        let evidence_url = evidence.url.clone();
+        record_evidence(
+            &mut ctx.accounts.evidence_record,
+            market,
+            ctx.accounts.resolver.key(),
+            outcome,
+            evidence,
+            ctx.bumps.evidence_record,
+        )?;
 
-        if outcome == OUTCOME_YES || outcome == OUTCOME_NO {
-            market.outcome = outcome;
-            market.resolved = true;
-            market.status = ResolutionStatus::Resolved;
-        } else {
-            market.status = ResolutionStatus::Disputed;
+        if to == ResolutionStatus::Proposed {
+            market.proposed_at = now;
+            emit!(ResolutionProposed {
+                market: market.key(),
+                outcome,
+                dispute_ends_at: market.dispute_ends_at(),
+            });
         }
 
         emit!(MarketResolved {
             market: market.key(),
             outcome,
-            evidence_url: evidence,
+            evidence_url,
             is_agent: true,
             reason,
         });
         Ok(())
     }
 
+    /// Creator override. The creator may hold a position, so on markets with
+    /// a dispute window the outcome is only proposed, like the resolver's.
     pub fn creator_resolve_market(
         ctx: Context<CreatorResolveMarket>,
         outcome: u8,
-        evidence: String,
+        evidence: Evidence,
+        manifest_hash: [u8; 32],
     ) -> Result<()> {
         let market = &mut ctx.accounts.market;
         let signer = &ctx.accounts.signer;
 
         require_keys_eq!(market.creator, signer.key(), MarketError::UnauthorizedCreator);
-        require!(
-            outcome == OUTCOME_YES || outcome == OUTCOME_NO,
-            MarketError::InvalidOutcome
-        );
+        market.require_manifest(&manifest_hash)?;
+        require!(!market.has_committee(), MarketError::CommitteeMarket);
+        require!(market.is_final_outcome(outcome), MarketError::InvalidOutcome);
+        let now = Clock::get()?.unix_timestamp;
+        market.require_direct_settlement(now)?;
 
-        market.outcome = outcome;
-        market.resolved = true;
+        settle_or_propose(market, outcome, now);
         market.agent_outcome = outcome;
-        market.status = ResolutionStatus::Resolved;
-        market.resolved_evidence_url = evidence.clone();
+        let evidence_url = evidence.url.clone();
+        record_evidence(
+            &mut ctx.accounts.evidence_record,
+            market,
+            signer.key(),
+            outcome,
+            evidence,
+            ctx.bumps.evidence_record,
+        )?;
 
         emit!(MarketResolved {
             market: market.key(),
             outcome,
-            evidence_url: evidence,
+            evidence_url,
             is_agent: false,
             reason: "CREATOR_OVERRIDE".to_string(),
         });
         Ok(())
     }
 
-    pub fn emergency_withdraw(ctx: Context<EmergencyWithdraw>) -> Result<()> {
-        let market = &mut ctx.accounts.market;
-        require!(market.resolved, MarketError::NotResolved);
-        require!(
-            ctx.accounts.resolver.key() == market.resolver,
-            MarketError::UnauthorizedResolver
+    /// Deposit USDC into the caller's resolver stake. Staking again cancels a
+    /// pending unstake request.
+    pub fn stake_resolver(ctx: Context<StakeResolver>, amount: u64) -> Result<()> {
+        require!(amount > 0, MarketError::InvalidAmount);
+        let cpi_ctx = CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.resolver_usdc.to_account_info(),
+                to: ctx.accounts.stake_vault.to_account_info(),
+                authority: ctx.accounts.resolver.to_account_info(),
+            },
         );
+        token::transfer(cpi_ctx, amount)?;
 
-        let winning_total = match market.outcome {
-            1 => market.yes_shares,
-            2 => market.no_shares,
-            _ => 0,
-        };
-        // Only allow when there were effectively no winning-side bets (just dust).
-        require!(winning_total <= 1, MarketError::NoWinningLiquidity);
+        let stake = &mut ctx.accounts.resolver_stake;
+        stake.resolver = ctx.accounts.resolver.key();
+        stake.vault = ctx.accounts.stake_vault.key();
+        stake.amount = stake
+            .amount
+            .checked_add(amount)
+            .ok_or(MarketError::MathOverflow)?;
+        stake.unlock_at = 0;
+        stake.bump = ctx.bumps.resolver_stake;
+        Ok(())
+    }
 
-        let amount = market.total_pool;
-        if amount == 0 {
-            return Ok(());
-        }
+    /// Start the unstake delay. The stake stays slashable until withdrawn, and
+    /// markets can no longer require it. Withdrawals also wait for every
+    /// proposal it backs to be finalized or arbitrated.
+    pub fn request_unstake(ctx: Context<RequestUnstake>) -> Result<()> {
+        let stake = &mut ctx.accounts.resolver_stake;
+        stake.unlock_at = Clock::get()?.unix_timestamp + RESOLVER_UNSTAKE_DELAY_SECS;
+        Ok(())
+    }
 
-        let question_hash = market.question_hash;
-        let seeds: &[&[u8]] = &[
-            b"market",
-            market.creator.as_ref(),
-            question_hash.as_ref(),
-            &[market.market_bump],
-        ];
-        let signer = &[&seeds[..]];
+    pub fn withdraw_stake(ctx: Context<WithdrawStake>, amount: u64) -> Result<()> {
+        let stake = &ctx.accounts.resolver_stake;
+        require!(
+            stake.unlock_at != 0
+                && Clock::get()?.unix_timestamp >= stake.unlock_at
+                && stake.open_proposals == 0,
+            MarketError::StakeLocked
+        );
+        require!(amount <= stake.amount, MarketError::InsufficientStake);
+
+        let resolver = stake.resolver;
+        let seeds: &[&[u8]] = &[b"resolver-stake", resolver.as_ref(), &[stake.bump]];
+        let signer = &[seeds];
         let cpi_ctx = CpiContext::new_with_signer(
             ctx.accounts.token_program.to_account_info(),
             Transfer {
-                from: ctx.accounts.vault.to_account_info(),
+                from: ctx.accounts.stake_vault.to_account_info(),
                 to: ctx.accounts.resolver_usdc.to_account_info(),
-                authority: market.to_account_info(),
+                authority: stake.to_account_info(),
             },
             signer,
         );
         token::transfer(cpi_ctx, amount)?;
 
-        market.total_pool = 0;
+        ctx.accounts.resolver_stake.amount -= amount;
         Ok(())
     }
-}
 
-#[derive(Accounts)]
-#[instruction(region_id: [u8; 32], question: String, close_time: i64, manifest_url: String, manifest_hash: [u8; 32])]
-pub struct CreateMarket<'info> {
-    #[account(mut)]
-    pub payer: Signer<'info>,
-    #[account(
-        init,
-        payer = payer,
-        space = 8 + Market::SIZE,
-        seeds = [b"market", payer.key().as_ref(), &question_hash(&question)],
-        bump
-    )]
-    pub market: Account<'info, Market>,
-    pub usdc_mint: Account<'info, Mint>,
-    #[account(
-        init,
-        payer = payer,
-        associated_token::mint = usdc_mint,
-        associated_token::authority = market
-    )]
-    pub vault: Account<'info, TokenAccount>,
-    /// CHECK: resolver authority
-    pub resolver: UncheckedAccount<'info>,
-    pub system_program: Program<'info, System>,
-    pub token_program: Program<'info, Token>,
-    pub associated_token_program: Program<'info, AssociatedToken>,
-    pub rent: Sysvar<'info, Rent>,
-}
+    /// Creator requires the market's resolver to keep at least `min_stake`
+    /// staked; its proposals are then checked against the registry and an
+    /// overturned proposal is slashed.
+    pub fn require_staked_resolver(
+        ctx: Context<RequireStakedResolver>,
+        min_stake: u64,
+    ) -> Result<()> {
+        let market = &ctx.accounts.market;
+        require!(
+            market.phase(Clock::get()?.unix_timestamp) == ResolutionStatus::Open,
+            MarketError::MarketClosed
+        );
+        require!(min_stake > 0, MarketError::InvalidAmount);
+        ctx.accounts.resolver_stake.require_active(min_stake)?;
+        ctx.accounts.market.min_resolver_stake = min_stake;
+        Ok(())
+    }
 
-#[derive(Accounts)]
-pub struct PlaceOrder<'info> {
-    #[account(mut)]
-    pub trader: Signer<'info>,
-    #[account(
-        seeds = [b"market", market.creator.as_ref(), market.question_hash.as_ref()],
-        bump = market.market_bump
-    )]
-    pub market: Account<'info, Market>,
-    pub system_program: Program<'info, System>,
-    /// CHECK: Anchor enforces owner and seeds; data layout matches ExternalUserLocationState
-    #[account(owner = ZK_LOCATION_PROGRAM_ID)]
-    pub user_location: Account<'info, ExternalUserLocationState>,
-    #[account(
-        init_if_needed,
-        payer = trader,
-        space = 8 + UserPosition::SIZE,
-        seeds = [b"user-position", market.key().as_ref(), trader.key().as_ref()],
-        bump
-    )]
+    /// Creator hands resolution to an M-of-N committee before close. From then
+    /// on only committee votes settle the market. A non-zero
+    /// `commit_window_secs` makes members vote by commit-reveal: commits are
+    /// accepted from close until `close_time + commit_window_secs`, reveals for
+    /// `reveal_window_secs` after that.
+    pub fn create_resolver_set(
+        ctx: Context<CreateResolverSet>,
+        members: Vec<Pubkey>,
+        threshold: u8,
+        commit_window_secs: i64,
+        reveal_window_secs: i64,
+    ) -> Result<()> {
+        let market = &ctx.accounts.market;
+        require!(
+            market.phase(Clock::get()?.unix_timestamp) == ResolutionStatus::Open,
+            MarketError::MarketClosed
+        );
+        require!(market.kind != MarketKind::Scalar, MarketError::CommitteeUnsupported);
+        require!(
+            !members.is_empty()
+                && members.len() <= MAX_RESOLVERS
+                && threshold >= 1
+                && threshold as usize <= members.len(),
+            MarketError::InvalidResolverSet
+        );
+        for (i, member) in members.iter().enumerate() {
+            require!(
+                !members[..i].contains(member),
+                MarketError::InvalidResolverSet
+            );
+        }
+        require!(
+            commit_window_secs >= 0
+                && reveal_window_secs >= 0
+                && (commit_window_secs == 0) == (reveal_window_secs == 0),
+            MarketError::InvalidResolverSet
+        );
+
+        let resolver_set = &mut ctx.accounts.resolver_set;
+        resolver_set.market = market.key();
+        resolver_set.threshold = threshold;
+        if commit_window_secs > 0 {
+            resolver_set.commit_ends_at = market
+                .close_time
+                .checked_add(commit_window_secs)
+                .ok_or(MarketError::MathOverflow)?;
+            resolver_set.reveal_ends_at = resolver_set
+                .commit_ends_at
+                .checked_add(reveal_window_secs)
+                .ok_or(MarketError::MathOverflow)?;
+        }
+        resolver_set.votes = vec![ResolverVote::default(); members.len()];
+        resolver_set.members = members;
+        resolver_set.bump = ctx.bumps.resolver_set;
+
+        ctx.accounts.market.resolver_set = resolver_set.key();
+        Ok(())
+    }
+
+    /// One vote per committee member; the market finalizes as soon as
+    /// `threshold` votes agree. Markets without a committee treat
+    /// `market.resolver` as a 1-of-1 committee, whose vote is only proposed
+    /// when the market has a dispute window.
+    pub fn submit_vote(
+        ctx: Context<SubmitVote>,
+        outcome: u8,
+        evidence_hash: [u8; 32],
+        manifest_hash: [u8; 32],
+    ) -> Result<()> {
+        let market = &ctx.accounts.market;
+        market.require_manifest(&manifest_hash)?;
+        require!(market.is_final_outcome(outcome), MarketError::InvalidOutcome);
+        let now = Clock::get()?.unix_timestamp;
+        market.require_direct_settlement(now)?;
+        let voter = ctx.accounts.voter.key();
+
+        let quorum = if market.has_committee() {
+            let resolver_set = ctx
+                .accounts
+                .resolver_set
+                .as_mut()
+                .ok_or(MarketError::MissingResolverSet)?;
+            require!(
+                !resolver_set.uses_commit_reveal(),
+                MarketError::CommitRevealRequired
+            );
+            require!(
+                now < resolver_set.voting_ends_at(market.close_time),
+                MarketError::VotingClosed
+            );
+            let member = resolver_set.member_index(&voter)?;
+            require!(
+                resolver_set.votes[member].outcome == OUTCOME_NONE,
+                MarketError::AlreadyVoted
+            );
+            resolver_set.votes[member].outcome = outcome;
+            resolver_set.votes[member].evidence_hash = evidence_hash;
+            resolver_set.has_quorum(outcome)
+        } else {
+            require_keys_eq!(voter, market.resolver, MarketError::UnauthorizedResolver);
+            false
+        };
+        let committee = market.has_committee();
+
+        emit!(VoteSubmitted {
+            market: market.key(),
+            voter,
+            outcome,
+            evidence_hash,
+        });
+
+        if quorum {
+            ctx.accounts.market.finalize(outcome);
+        } else if !committee {
+            settle_as_resolver(&mut ctx.accounts.market, outcome, now)?;
+        }
+        Ok(())
+    }
+
+    /// Commit-reveal committees: record `keccak(outcome || salt)` during the
+    /// commit phase. Identical commitments are rejected so a member cannot
+    /// copy another's commit and replay its reveal.
+    pub fn commit_vote(ctx: Context<CommitVote>, commitment: [u8; 32]) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        ctx.accounts.market.require_direct_settlement(now)?;
+        let resolver_set = &mut ctx.accounts.resolver_set;
+        require!(
+            resolver_set.uses_commit_reveal(),
+            MarketError::CommitRevealNotEnabled
+        );
+        require!(now < resolver_set.commit_ends_at, MarketError::NotCommitPhase);
+        require!(commitment != [0u8; 32], MarketError::InvalidCommitment);
+        let member = resolver_set.member_index(&ctx.accounts.voter.key())?;
+        require!(
+            resolver_set.votes[member].commitment == [0u8; 32],
+            MarketError::AlreadyVoted
+        );
+        require!(
+            resolver_set.votes.iter().all(|v| v.commitment != commitment),
+            MarketError::InvalidCommitment
+        );
+        resolver_set.votes[member].commitment = commitment;
+
+        emit!(VoteCommitted {
+            market: resolver_set.market,
+            voter: ctx.accounts.voter.key(),
+            commitment,
+        });
+        Ok(())
+    }
+
+    /// Open a commitment during the reveal phase. The market finalizes as soon
+    /// as `threshold` revealed votes agree.
+    pub fn reveal_vote(
+        ctx: Context<RevealVote>,
+        outcome: u8,
+        salt: [u8; 32],
+        evidence_hash: [u8; 32],
+        manifest_hash: [u8; 32],
+    ) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let market = &ctx.accounts.market;
+        market.require_manifest(&manifest_hash)?;
+        require!(market.is_final_outcome(outcome), MarketError::InvalidOutcome);
+        market.require_direct_settlement(now)?;
+        let resolver_set = &mut ctx.accounts.resolver_set;
+        require!(
+            resolver_set.uses_commit_reveal(),
+            MarketError::CommitRevealNotEnabled
+        );
+        require!(
+            now >= resolver_set.commit_ends_at && now < resolver_set.reveal_ends_at,
+            MarketError::NotRevealPhase
+        );
+        let voter = ctx.accounts.voter.key();
+        let member = resolver_set.member_index(&voter)?;
+        let vote = &mut resolver_set.votes[member];
+        require!(vote.outcome == OUTCOME_NONE, MarketError::AlreadyVoted);
+        require!(
+            vote.commitment == vote_commitment(outcome, &salt),
+            MarketError::InvalidCommitment
+        );
+        vote.outcome = outcome;
+        vote.evidence_hash = evidence_hash;
+        let quorum = resolver_set.has_quorum(outcome);
+
+        emit!(VoteSubmitted {
+            market: market.key(),
+            voter,
+            outcome,
+            evidence_hash,
+        });
+
+        if quorum {
+            ctx.accounts.market.finalize(outcome);
+        }
+        Ok(())
+    }
+
+    /// Permissionless, once voting is over (the reveal phase, or
+    /// COMMITTEE_VOTE_WINDOW_SECS after close without commit-reveal): missing
+    /// votes count as abstentions. Finalizes on the leading outcome if it
+    /// reached the threshold, otherwise marks the market Disputed for the
+    /// arbiter.
+    pub fn tally_votes(ctx: Context<TallyVotes>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let resolver_set = &ctx.accounts.resolver_set;
+        let ends_at = resolver_set.voting_ends_at(ctx.accounts.market.close_time);
+        if resolver_set.uses_commit_reveal() {
+            require!(now >= ends_at, MarketError::NotRevealPhase);
+        } else {
+            require!(now >= ends_at, MarketError::VotingOpen);
+        }
+
+        let market = &mut ctx.accounts.market;
+        let from = market.phase(now);
+        match resolver_set.leading_outcome() {
+            Some(outcome) if resolver_set.has_quorum(outcome) => {
+                check_transition(from, ResolutionStatus::Finalized)?;
+                market.finalize(outcome);
+            }
+            _ => {
+                check_transition(from, ResolutionStatus::Disputed)?;
+                market.status = ResolutionStatus::Disputed;
+            }
+        }
+        Ok(())
+    }
+
+    /// Object to a proposed agent outcome during the dispute window. The
+    /// challenger must hold a position in the market and posts the market's
+    /// challenge bond; the market escalates to its arbiter.
+    pub fn challenge_resolution(ctx: Context<ChallengeResolution>) -> Result<()> {
+        let market = &ctx.accounts.market;
+        let from = market.phase(Clock::get()?.unix_timestamp);
+        require!(from == ResolutionStatus::Proposed, MarketError::NotProposed);
+        check_transition(from, ResolutionStatus::Disputed)?;
+        require!(market.arbiter != Pubkey::default(), MarketError::NoArbiter);
+        require!(
+            Clock::get()?.unix_timestamp < market.dispute_ends_at(),
+            MarketError::DisputeWindowClosed
+        );
+
+        let bond = market.challenge_bond;
+        if bond > 0 {
+            let cpi_ctx = CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.challenger_usdc.to_account_info(),
+                    to: ctx.accounts.bond_vault.to_account_info(),
+                    authority: ctx.accounts.challenger.to_account_info(),
+                },
+            );
+            token::transfer(cpi_ctx, bond)?;
+        }
+
+        let market = &mut ctx.accounts.market;
+        market.status = ResolutionStatus::Disputed;
+        market.challenger = ctx.accounts.challenger.key();
+
+        emit!(ResolutionChallenged {
+            market: market.key(),
+            challenger: market.challenger,
+            bond,
+        });
+        Ok(())
+    }
+
+    /// Permissionless: settles an unchallenged proposal once the window has passed.
+    pub fn finalize_resolution(ctx: Context<FinalizeResolution>) -> Result<()> {
+        let market = &mut ctx.accounts.market;
+        let now = Clock::get()?.unix_timestamp;
+        let from = market.phase(now);
+        require!(from == ResolutionStatus::Proposed, MarketError::NotProposed);
+        check_transition(from, ResolutionStatus::Finalized)?;
+        require!(
+            now >= market.dispute_ends_at(),
+            MarketError::DisputeWindowOpen
+        );
+
+        if market.stake_locked {
+            ctx.accounts
+                .resolver_stake
+                .as_mut()
+                .ok_or(MarketError::MissingResolverStake)?
+                .release_proposal();
+            market.stake_locked = false;
+        }
+        let outcome = market.agent_outcome;
+        market.finalize(outcome);
+        Ok(())
+    }
+
+    /// Arbiter settles a disputed market. If a challenge was posted, the bond
+    /// returns to the challenger when the arbiter overturns the agent, and is
+    /// slashed into the protocol fee vault when the agent is upheld.
+    pub fn arbitrate_resolution(
+        ctx: Context<ArbitrateResolution>,
+        outcome: u8,
+        manifest_hash: [u8; 32],
+    ) -> Result<()> {
+        let market = &ctx.accounts.market;
+        market.require_manifest(&manifest_hash)?;
+        let from = market.phase(Clock::get()?.unix_timestamp);
+        check_transition(from, ResolutionStatus::Finalized)?;
+        require!(from == ResolutionStatus::Disputed, MarketError::NotDisputed);
+        require!(market.is_final_outcome(outcome), MarketError::InvalidOutcome);
+
+        if market.challenger != Pubkey::default() {
+            let upheld = outcome == market.agent_outcome;
+            settle_challenge(ctx.accounts, upheld)?;
+        }
+
+        ctx.accounts.market.finalize(outcome);
+        Ok(())
+    }
+
+    /// Arbiter settles a disputed scalar market on `value`, clamped to the
+    /// bounds. A challenged proposal is upheld when it proposed this value.
+    pub fn arbitrate_scalar_resolution(
+        ctx: Context<ArbitrateResolution>,
+        value: i64,
+        manifest_hash: [u8; 32],
+    ) -> Result<()> {
+        let market = &ctx.accounts.market;
+        market.require_manifest(&manifest_hash)?;
+        require!(market.kind == MarketKind::Scalar, MarketError::NotScalarMarket);
+        let from = market.phase(Clock::get()?.unix_timestamp);
+        check_transition(from, ResolutionStatus::Finalized)?;
+        require!(from == ResolutionStatus::Disputed, MarketError::NotDisputed);
+        let value = value.clamp(market.lower_bound, market.upper_bound);
+
+        if market.challenger != Pubkey::default() {
+            let upheld = market.agent_outcome == OUTCOME_NONE && market.resolved_value == value;
+            settle_challenge(ctx.accounts, upheld)?;
+        }
+
+        let market = &mut ctx.accounts.market;
+        market.finalize(OUTCOME_NONE);
+        market.resolved_value = value;
+        Ok(())
+    }
+
+    pub fn withdraw_creator_fees(ctx: Context<WithdrawCreatorFees>) -> Result<()> {
+        let market = &ctx.accounts.market;
+        let amount = ctx.accounts.creator_fee_vault.amount;
+        if amount == 0 {
+            return Ok(());
+        }
+
+        let question_hash = market.question_hash;
+        let seeds: &[&[u8]] = &[
+            b"market",
+            market.creator.as_ref(),
+            question_hash.as_ref(),
+            &[market.market_bump],
+        ];
+        let signer = &[seeds];
+        let cpi_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.creator_fee_vault.to_account_info(),
+                to: ctx.accounts.creator_usdc.to_account_info(),
+                authority: market.to_account_info(),
+            },
+            signer,
+        );
+        token::transfer(cpi_ctx, amount)
+    }
+
+    /// Permissionless: always pays the configured treasury.
+    pub fn withdraw_protocol_fees(ctx: Context<WithdrawProtocolFees>) -> Result<()> {
+        let market = &ctx.accounts.market;
+        let amount = ctx.accounts.protocol_fee_vault.amount;
+        if amount == 0 {
+            return Ok(());
+        }
+
+        let question_hash = market.question_hash;
+        let seeds: &[&[u8]] = &[
+            b"market",
+            market.creator.as_ref(),
+            question_hash.as_ref(),
+            &[market.market_bump],
+        ];
+        let signer = &[seeds];
+        let cpi_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.protocol_fee_vault.to_account_info(),
+                to: ctx.accounts.treasury_usdc.to_account_info(),
+                authority: market.to_account_info(),
+            },
+            signer,
+        );
+        token::transfer(cpi_ctx, amount)
+    }
+
+    pub fn emergency_withdraw(ctx: Context<EmergencyWithdraw>) -> Result<()> {
+        let market = &mut ctx.accounts.market;
+        require!(
+            market.phase(Clock::get()?.unix_timestamp) == ResolutionStatus::Finalized,
+            MarketError::NotResolved
+        );
+        require!(
+            ctx.accounts.resolver.key() == market.resolver,
+            MarketError::UnauthorizedResolver
+        );
+        // LMSR vaults hold the creator's subsidy; see withdraw_lmsr_surplus.
+        require!(
+            market.pricing_model == PricingModel::Dpm,
+            MarketError::UnsupportedPricingModel
+        );
+
+        if market.outcome == OUTCOME_INVALID
+            && (market.is_tokenized() || !market.cost_basis_tracked)
+        {
+            // Shares are refunded unless only the priors exist.
+            require!(
+                (0..market.num_outcomes()).all(|index| market.shares(index) <= 1),
+                MarketError::NoWinningLiquidity
+            );
+        } else if market.outcome == OUTCOME_INVALID {
+            // Refunds are owed unless nobody has a recorded cost basis.
+            require!(
+                market.total_cost_basis == 0,
+                MarketError::NoWinningLiquidity
+            );
+        } else if market.kind == MarketKind::Scalar {
+            // Every side that is owed part of the pool must hold only dust.
+            let (long_pool, short_pool) = scalar_pools(market)?;
+            require!(
+                (long_pool == 0 || market.shares(0) <= 1)
+                    && (short_pool == 0 || market.shares(1) <= 1),
+                MarketError::NoWinningLiquidity
+            );
+        } else {
+            let winning_total = if market.is_valid_outcome(market.outcome) {
+                market.shares((market.outcome - 1) as usize)
+            } else {
+                0
+            };
+            // Only allow when there were effectively no winning-side bets (just dust).
+            require!(winning_total <= 1, MarketError::NoWinningLiquidity);
+        }
+
+        let amount = market.total_pool;
+        if amount == 0 {
+            return Ok(());
+        }
+
+        let question_hash = market.question_hash;
+        let seeds: &[&[u8]] = &[
+            b"market",
+            market.creator.as_ref(),
+            question_hash.as_ref(),
+            &[market.market_bump],
+        ];
+        let signer = &[seeds];
+        let cpi_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.vault.to_account_info(),
+                to: ctx.accounts.resolver_usdc.to_account_info(),
+                authority: market.to_account_info(),
+            },
+            signer,
+        );
+        token::transfer(cpi_ctx, amount)?;
+
+        market.total_pool = 0;
+        Ok(())
+    }
+
+    /// LMSR markets: once finalized, the creator takes back whatever the vault
+    /// holds beyond what claims are owed. Safe to call again; later calls
+    /// find no surplus.
+    pub fn withdraw_lmsr_surplus(ctx: Context<WithdrawLmsrSurplus>) -> Result<()> {
+        let market = &ctx.accounts.market;
+        require!(
+            market.pricing_model == PricingModel::Lmsr,
+            MarketError::UnsupportedPricingModel
+        );
+        require!(
+            market.phase(Clock::get()?.unix_timestamp) == ResolutionStatus::Finalized,
+            MarketError::NotResolved
+        );
+        let owed = lmsr_total_claims(market)?.min(market.total_pool as u128) as u64;
+        let surplus = market.total_pool - owed;
+        if surplus == 0 {
+            return Ok(());
+        }
+
+        let question_hash = market.question_hash;
+        let seeds: &[&[u8]] = &[
+            b"market",
+            market.creator.as_ref(),
+            question_hash.as_ref(),
+            &[market.market_bump],
+        ];
+        let signer = &[seeds];
+        let cpi_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.vault.to_account_info(),
+                to: ctx.accounts.creator_usdc.to_account_info(),
+                authority: market.to_account_info(),
+            },
+            signer,
+        );
+        token::transfer(cpi_ctx, surplus)?;
+
+        ctx.accounts.market.total_pool = owed;
+        Ok(())
+    }
+
+    /// Opt a binary market into SPL share tokens before its first trade. YES and
+    /// NO become mints with the market PDA as authority; from then on
+    /// `place_order` mints shares to the trader's token account and
+    /// `sell_shares`/`claim` burn them instead of touching `UserPosition`.
+    pub fn enable_share_tokens(ctx: Context<EnableShareTokens>) -> Result<()> {
+        let market = &mut ctx.accounts.market;
+        require!(
+            market.phase(Clock::get()?.unix_timestamp) == ResolutionStatus::Open,
+            MarketError::MarketClosed
+        );
+        require!(market.total_pool == 0, MarketError::MarketHasTrades);
+        require!(
+            market.kind == MarketKind::Outcomes
+                && !market.is_categorical()
+                && market.pricing_model == PricingModel::Dpm,
+            MarketError::ShareTokensUnsupported
+        );
+        market.share_mints = [ctx.accounts.yes_mint.key(), ctx.accounts.no_mint.key()];
+        Ok(())
+    }
+
+    /// Creator allocates an on-chain copy of the manifest, `len` bytes long,
+    /// to be filled by `write_manifest_chunk` and checked by `seal_manifest`.
+    pub fn init_manifest_store(ctx: Context<InitManifestStore>, len: u32) -> Result<()> {
+        require!(
+            len as usize <= MAX_ONCHAIN_MANIFEST_LEN,
+            MarketError::ManifestTooLarge
+        );
+        let store = &mut ctx.accounts.manifest_store;
+        store.market = ctx.accounts.market.key();
+        store.sealed = false;
+        store.bump = ctx.bumps.manifest_store;
+        store.data = vec![0; len as usize];
+        Ok(())
+    }
+
+    pub fn write_manifest_chunk(
+        ctx: Context<WriteManifest>,
+        offset: u32,
+        chunk: Vec<u8>,
+    ) -> Result<()> {
+        ctx.accounts.manifest_store.write(offset as usize, &chunk)
+    }
+
+    /// Freezes the store once its bytes hash to `market.manifest_hash`.
+    pub fn seal_manifest(ctx: Context<WriteManifest>) -> Result<()> {
+        let manifest_hash = ctx.accounts.market.manifest_hash;
+        ctx.accounts.manifest_store.seal(&manifest_hash)
+    }
+
+    /// Permissionless: grows a market created under an older, smaller layout to
+    /// `Market::SIZE`. Appended fields read as zero, which keeps legacy behaviour.
+    pub fn migrate_market(ctx: Context<MigrateMarket>) -> Result<()> {
+        grow_account(
+            &ctx.accounts.market.to_account_info(),
+            Market::DISCRIMINATOR,
+            8 + Market::SIZE,
+            &ctx.accounts.payer,
+            &ctx.accounts.system_program,
+        )
+    }
+
+    /// Grows a position created under an older layout to `UserPosition::SIZE`.
+    pub fn migrate_position(ctx: Context<MigratePosition>) -> Result<()> {
+        grow_account(
+            &ctx.accounts.user_position.to_account_info(),
+            UserPosition::DISCRIMINATOR,
+            8 + UserPosition::SIZE,
+            &ctx.accounts.trader,
+            &ctx.accounts.system_program,
+        )
+    }
+}
+
+#[derive(Accounts)]
+pub struct InitProtocolConfig<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + ProtocolConfig::SIZE,
+        seeds = [b"protocol-config"],
+        bump
+    )]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+    #[account(constraint = program.programdata_address()? == Some(program_data.key()))]
+    pub program: Program<'info, crate::program::HyperlocalMarkets>,
+    #[account(
+        constraint = program_data.upgrade_authority_address == Some(admin.key())
+            @ MarketError::UnauthorizedAdmin
+    )]
+    pub program_data: Account<'info, ProgramData>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateProtocolConfig<'info> {
+    pub admin: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"protocol-config"],
+        bump = protocol_config.bump,
+        has_one = admin @ MarketError::UnauthorizedAdmin
+    )]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+}
+
+#[derive(Accounts)]
+pub struct SetDisputeConfig<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    #[account(
+        seeds = [b"protocol-config"],
+        bump = protocol_config.bump,
+        has_one = admin @ MarketError::UnauthorizedAdmin
+    )]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = 8 + DisputeConfig::SIZE,
+        seeds = [b"dispute-config"],
+        bump
+    )]
+    pub dispute_config: Account<'info, DisputeConfig>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(region_id: [u8; 32], question: String)]
+pub struct CreateMarket<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + Market::SIZE,
+        seeds = [b"market", payer.key().as_ref(), &question_hash(&question)],
+        bump
+    )]
+    pub market: Account<'info, Market>,
+    pub usdc_mint: Account<'info, Mint>,
+    #[account(
+        init,
+        payer = payer,
+        associated_token::mint = usdc_mint,
+        associated_token::authority = market
+    )]
+    pub vault: Account<'info, TokenAccount>,
+    #[account(seeds = [b"protocol-config"], bump = protocol_config.bump)]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+    #[account(
+        init,
+        payer = payer,
+        seeds = [b"protocol-fees", market.key().as_ref()],
+        bump,
+        token::mint = usdc_mint,
+        token::authority = market
+    )]
+    pub protocol_fee_vault: Box<Account<'info, TokenAccount>>,
+    #[account(
+        init,
+        payer = payer,
+        seeds = [b"creator-fees", market.key().as_ref()],
+        bump,
+        token::mint = usdc_mint,
+        token::authority = market
+    )]
+    pub creator_fee_vault: Box<Account<'info, TokenAccount>>,
+    #[account(seeds = [b"dispute-config"], bump = dispute_config.bump)]
+    pub dispute_config: Box<Account<'info, DisputeConfig>>,
+    /// CHECK: resolver authority
+    pub resolver: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub rent: Sysvar<'info, Rent>,
+    // Required when seeding initial liquidity.
+    #[account(mut, constraint = creator_usdc.mint == usdc_mint.key())]
+    pub creator_usdc: Option<Box<Account<'info, TokenAccount>>>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + UserPosition::SIZE,
+        seeds = [b"user-position", market.key().as_ref(), payer.key().as_ref()],
+        bump
+    )]
+    pub creator_position: Option<Box<Account<'info, UserPosition>>>,
+}
+
+#[derive(Accounts)]
+pub struct PlaceOrder<'info> {
+    #[account(mut)]
+    pub trader: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"market", market.creator.as_ref(), market.question_hash.as_ref()],
+        bump = market.market_bump
+    )]
+    pub market: Account<'info, Market>,
+    pub system_program: Program<'info, System>,
+    #[account(owner = ZK_LOCATION_PROGRAM_ID)]
+    pub user_location: Account<'info, UserLocationState>,
+    #[account(
+        init_if_needed,
+        payer = trader,
+        space = 8 + UserPosition::SIZE,
+        seeds = [b"user-position", market.key().as_ref(), trader.key().as_ref()],
+        bump
+    )]
+    pub user_position: Account<'info, UserPosition>,
+    #[account(mut, constraint = trader_usdc.mint == market.usdc_mint)]
+    pub trader_usdc: Account<'info, TokenAccount>,
+    #[account(mut, address = market.vault)]
+    pub vault: Account<'info, TokenAccount>,
+    // Optional so markets created before fees existed still trade; required when a fee applies.
+    #[account(mut, address = market.protocol_fee_vault)]
+    pub protocol_fee_vault: Option<Account<'info, TokenAccount>>,
+    #[account(mut, address = market.creator_fee_vault)]
+    pub creator_fee_vault: Option<Account<'info, TokenAccount>>,
+    pub token_program: Program<'info, Token>,
+    // Tokenized markets only: the bought side's mint and the trader's account for it.
+    #[account(mut)]
+    pub share_mint: Option<Account<'info, Mint>>,
+    #[account(mut)]
+    pub trader_shares: Option<Account<'info, TokenAccount>>,
+}
+
+#[derive(Accounts)]
+pub struct SellShares<'info> {
+    pub trader: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"market", market.creator.as_ref(), market.question_hash.as_ref()],
+        bump = market.market_bump
+    )]
+    pub market: Account<'info, Market>,
+    // Untokenized markets only.
+    #[account(
+        mut,
+        seeds = [b"user-position", market.key().as_ref(), trader.key().as_ref()],
+        bump = user_position.bump
+    )]
+    pub user_position: Option<Account<'info, UserPosition>>,
+    #[account(mut, constraint = trader_usdc.mint == market.usdc_mint)]
+    pub trader_usdc: Account<'info, TokenAccount>,
+    #[account(mut, address = market.vault)]
+    pub vault: Account<'info, TokenAccount>,
+    pub token_program: Program<'info, Token>,
+    // Tokenized markets only: the side's mint and the holder's account for it.
+    #[account(mut)]
+    pub share_mint: Option<Account<'info, Mint>>,
+    #[account(mut)]
+    pub trader_shares: Option<Account<'info, TokenAccount>>,
+}
+
+#[derive(Accounts)]
+pub struct Quote<'info> {
+    #[account(
+        seeds = [b"market", market.creator.as_ref(), market.question_hash.as_ref()],
+        bump = market.market_bump
+    )]
+    pub market: Account<'info, Market>,
+}
+
+#[derive(Accounts)]
+pub struct ResolveMarket<'info> {
+    #[account(
+        mut,
+        seeds = [b"market", market.creator.as_ref(), market.question_hash.as_ref()],
+        bump = market.market_bump
+    )]
+    pub market: Account<'info, Market>,
+    pub resolver: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct Claim<'info> {
+    #[account(mut)]
+    pub trader: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"market", market.creator.as_ref(), market.question_hash.as_ref()],
+        bump = market.market_bump
+    )]
+    pub market: Account<'info, Market>,
+    // Untokenized markets only.
+    #[account(
+        mut,
+        seeds = [b"user-position", market.key().as_ref(), trader.key().as_ref()],
+        bump = user_position.bump
+    )]
+    pub user_position: Option<Account<'info, UserPosition>>,
+    #[account(mut, constraint = trader_usdc.mint == market.usdc_mint)]
+    pub trader_usdc: Account<'info, TokenAccount>,
+    #[account(mut, address = market.vault)]
+    pub vault: Account<'info, TokenAccount>,
+    pub token_program: Program<'info, Token>,
+    // Tokenized markets only: the side's mint and the holder's account for it.
+    #[account(mut)]
+    pub share_mint: Option<Account<'info, Mint>>,
+    #[account(mut)]
+    pub trader_shares: Option<Account<'info, TokenAccount>>,
+}
+
+#[derive(Accounts)]
+pub struct AgentAttemptResolution<'info> {
+    #[account(
+        mut,
+        seeds = [b"market", market.creator.as_ref(), market.question_hash.as_ref()],
+        bump = market.market_bump,
+        has_one = resolver
+    )]
+    pub market: Account<'info, Market>,
+    /// CHECK: resolver authority (AI agent)
+    #[account(mut, signer)]
+    pub resolver: AccountInfo<'info>,
+    #[account(
+        init,
+        payer = resolver,
+        space = 8 + EvidenceRecord::SIZE,
+        seeds = [b"evidence", market.key().as_ref(), &market.evidence_count.to_le_bytes()],
+        bump
+    )]
+    pub evidence_record: Account<'info, EvidenceRecord>,
+    pub system_program: Program<'info, System>,
+    // Required when the market sets min_resolver_stake.
+    #[account(
+        mut,
+        seeds = [b"resolver-stake", resolver.key().as_ref()],
+        bump = resolver_stake.bump
+    )]
+    pub resolver_stake: Option<Account<'info, ResolverStake>>,
+}
+
+#[derive(Accounts)]
+pub struct CreatorResolveMarket<'info> {
+    #[account(
+        mut,
+        seeds = [b"market", market.creator.as_ref(), market.question_hash.as_ref()],
+        bump = market.market_bump
+    )]
+    pub market: Account<'info, Market>,
+    #[account(mut)]
+    pub signer: Signer<'info>,
+    #[account(
+        init,
+        payer = signer,
+        space = 8 + EvidenceRecord::SIZE,
+        seeds = [b"evidence", market.key().as_ref(), &market.evidence_count.to_le_bytes()],
+        bump
+    )]
+    pub evidence_record: Account<'info, EvidenceRecord>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct StakeResolver<'info> {
+    #[account(mut)]
+    pub resolver: Signer<'info>,
+    pub usdc_mint: Account<'info, Mint>,
+    #[account(
+        init_if_needed,
+        payer = resolver,
+        space = 8 + ResolverStake::SIZE,
+        seeds = [b"resolver-stake", resolver.key().as_ref()],
+        bump
+    )]
+    pub resolver_stake: Account<'info, ResolverStake>,
+    #[account(
+        init_if_needed,
+        payer = resolver,
+        seeds = [b"resolver-stake-vault", resolver.key().as_ref()],
+        bump,
+        token::mint = usdc_mint,
+        token::authority = resolver_stake
+    )]
+    pub stake_vault: Account<'info, TokenAccount>,
+    #[account(mut, constraint = resolver_usdc.mint == usdc_mint.key())]
+    pub resolver_usdc: Account<'info, TokenAccount>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RequestUnstake<'info> {
+    pub resolver: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"resolver-stake", resolver.key().as_ref()],
+        bump = resolver_stake.bump,
+        has_one = resolver
+    )]
+    pub resolver_stake: Account<'info, ResolverStake>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawStake<'info> {
+    pub resolver: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"resolver-stake", resolver.key().as_ref()],
+        bump = resolver_stake.bump,
+        has_one = resolver
+    )]
+    pub resolver_stake: Account<'info, ResolverStake>,
+    #[account(mut, address = resolver_stake.vault)]
+    pub stake_vault: Account<'info, TokenAccount>,
+    #[account(mut, constraint = resolver_usdc.mint == stake_vault.mint)]
+    pub resolver_usdc: Account<'info, TokenAccount>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct RequireStakedResolver<'info> {
+    pub creator: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"market", market.creator.as_ref(), market.question_hash.as_ref()],
+        bump = market.market_bump,
+        has_one = creator @ MarketError::UnauthorizedCreator
+    )]
+    pub market: Account<'info, Market>,
+    #[account(
+        seeds = [b"resolver-stake", market.resolver.as_ref()],
+        bump = resolver_stake.bump
+    )]
+    pub resolver_stake: Account<'info, ResolverStake>,
+    // Stake must be in the market's collateral mint to be slashable into it.
+    #[account(address = resolver_stake.vault, constraint = stake_vault.mint == market.usdc_mint)]
+    pub stake_vault: Account<'info, TokenAccount>,
+}
+
+#[derive(Accounts)]
+pub struct CreateResolverSet<'info> {
+    #[account(mut)]
+    pub creator: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"market", market.creator.as_ref(), market.question_hash.as_ref()],
+        bump = market.market_bump,
+        has_one = creator @ MarketError::UnauthorizedCreator
+    )]
+    pub market: Account<'info, Market>,
+    #[account(
+        init,
+        payer = creator,
+        space = 8 + ResolverSet::SIZE,
+        seeds = [b"resolver-set", market.key().as_ref()],
+        bump
+    )]
+    pub resolver_set: Account<'info, ResolverSet>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SubmitVote<'info> {
+    pub voter: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"market", market.creator.as_ref(), market.question_hash.as_ref()],
+        bump = market.market_bump
+    )]
+    pub market: Account<'info, Market>,
+    // Required when the market has a committee; omitted for the 1-of-1 case.
+    #[account(mut, address = market.resolver_set)]
+    pub resolver_set: Option<Account<'info, ResolverSet>>,
+}
+
+#[derive(Accounts)]
+pub struct CommitVote<'info> {
+    pub voter: Signer<'info>,
+    #[account(
+        seeds = [b"market", market.creator.as_ref(), market.question_hash.as_ref()],
+        bump = market.market_bump
+    )]
+    pub market: Account<'info, Market>,
+    #[account(mut, address = market.resolver_set)]
+    pub resolver_set: Account<'info, ResolverSet>,
+}
+
+#[derive(Accounts)]
+pub struct RevealVote<'info> {
+    pub voter: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"market", market.creator.as_ref(), market.question_hash.as_ref()],
+        bump = market.market_bump
+    )]
+    pub market: Account<'info, Market>,
+    #[account(mut, address = market.resolver_set)]
+    pub resolver_set: Account<'info, ResolverSet>,
+}
+
+#[derive(Accounts)]
+pub struct TallyVotes<'info> {
+    #[account(
+        mut,
+        seeds = [b"market", market.creator.as_ref(), market.question_hash.as_ref()],
+        bump = market.market_bump
+    )]
+    pub market: Account<'info, Market>,
+    #[account(address = market.resolver_set)]
+    pub resolver_set: Account<'info, ResolverSet>,
+}
+
+#[derive(Accounts)]
+pub struct ChallengeResolution<'info> {
+    #[account(mut)]
+    pub challenger: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"market", market.creator.as_ref(), market.question_hash.as_ref()],
+        bump = market.market_bump
+    )]
+    pub market: Account<'info, Market>,
+    // Only traders in this market may challenge.
+    #[account(
+        seeds = [b"user-position", market.key().as_ref(), challenger.key().as_ref()],
+        bump = user_position.bump
+    )]
     pub user_position: Account<'info, UserPosition>,
-    #[account(mut, constraint = trader_usdc.mint == market.usdc_mint)]
-    pub trader_usdc: Account<'info, TokenAccount>,
-    #[account(mut, address = market.vault)]
-    pub vault: Account<'info, TokenAccount>,
+    #[account(address = market.usdc_mint)]
+    pub usdc_mint: Account<'info, Mint>,
+    #[account(
+        init,
+        payer = challenger,
+        seeds = [b"challenge-bond", market.key().as_ref()],
+        bump,
+        token::mint = usdc_mint,
+        token::authority = market
+    )]
+    pub bond_vault: Account<'info, TokenAccount>,
+    #[account(mut, constraint = challenger_usdc.mint == market.usdc_mint)]
+    pub challenger_usdc: Account<'info, TokenAccount>,
     pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-pub struct ResolveMarket<'info> {
+pub struct FinalizeResolution<'info> {
     #[account(
         mut,
         seeds = [b"market", market.creator.as_ref(), market.question_hash.as_ref()],
         bump = market.market_bump
     )]
     pub market: Account<'info, Market>,
-    pub resolver: Signer<'info>,
+    // Required when the market sets min_resolver_stake; releases the proposal.
+    #[account(
+        mut,
+        seeds = [b"resolver-stake", market.resolver.as_ref()],
+        bump = resolver_stake.bump
+    )]
+    pub resolver_stake: Option<Account<'info, ResolverStake>>,
 }
 
 #[derive(Accounts)]
-pub struct Claim<'info> {
-    #[account(mut)]
-    pub trader: Signer<'info>,
+pub struct ArbitrateResolution<'info> {
+    pub arbiter: Signer<'info>,
     #[account(
         mut,
         seeds = [b"market", market.creator.as_ref(), market.question_hash.as_ref()],
-        bump = market.market_bump
+        bump = market.market_bump,
+        has_one = arbiter @ MarketError::UnauthorizedArbiter
     )]
     pub market: Account<'info, Market>,
+    // Bond accounts are only needed when the market was challenged.
+    #[account(mut, seeds = [b"challenge-bond", market.key().as_ref()], bump)]
+    pub bond_vault: Option<Account<'info, TokenAccount>>,
+    #[account(mut, constraint = challenger_usdc.owner == market.challenger @ MarketError::MissingBondAccount)]
+    pub challenger_usdc: Option<Account<'info, TokenAccount>>,
+    #[account(mut, address = market.protocol_fee_vault)]
+    pub protocol_fee_vault: Option<Account<'info, TokenAccount>>,
+    // Required for challenged staked markets: releases the proposal, and is
+    // slashed when the resolver's outcome is overturned.
     #[account(
         mut,
-        seeds = [b"user-position", market.key().as_ref(), trader.key().as_ref()],
-        bump = user_position.bump
+        seeds = [b"resolver-stake", market.resolver.as_ref()],
+        bump = resolver_stake.bump
     )]
-    pub user_position: Account<'info, UserPosition>,
-    #[account(mut, constraint = trader_usdc.mint == market.usdc_mint)]
-    pub trader_usdc: Account<'info, TokenAccount>,
-    #[account(mut, address = market.vault)]
-    pub vault: Account<'info, TokenAccount>,
+    pub resolver_stake: Option<Account<'info, ResolverStake>>,
+    #[account(mut, seeds = [b"resolver-stake-vault", market.resolver.as_ref()], bump)]
+    pub resolver_stake_vault: Option<Account<'info, TokenAccount>>,
     pub token_program: Program<'info, Token>,
 }
 
 #[derive(Accounts)]
-pub struct AgentAttemptResolution<'info> {
-    #[account(mut, has_one = resolver)]
+pub struct WithdrawCreatorFees<'info> {
+    pub creator: Signer<'info>,
+    #[account(
+        seeds = [b"market", market.creator.as_ref(), market.question_hash.as_ref()],
+        bump = market.market_bump,
+        has_one = creator @ MarketError::UnauthorizedCreator
+    )]
     pub market: Account<'info, Market>,
-    /// CHECK: resolver authority (AI agent)
-    #[account(signer)]
-    pub resolver: AccountInfo<'info>,
+    #[account(mut, address = market.creator_fee_vault)]
+    pub creator_fee_vault: Account<'info, TokenAccount>,
+    #[account(mut, constraint = creator_usdc.mint == market.usdc_mint)]
+    pub creator_usdc: Account<'info, TokenAccount>,
+    pub token_program: Program<'info, Token>,
 }
 
 #[derive(Accounts)]
-pub struct CreatorResolveMarket<'info> {
-    #[account(mut)]
+pub struct WithdrawProtocolFees<'info> {
+    #[account(
+        seeds = [b"market", market.creator.as_ref(), market.question_hash.as_ref()],
+        bump = market.market_bump
+    )]
     pub market: Account<'info, Market>,
-    pub signer: Signer<'info>,
+    #[account(seeds = [b"protocol-config"], bump = protocol_config.bump)]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+    #[account(mut, address = market.protocol_fee_vault)]
+    pub protocol_fee_vault: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = treasury_usdc.owner == protocol_config.treasury @ MarketError::InvalidTreasury,
+        constraint = treasury_usdc.mint == market.usdc_mint
+    )]
+    pub treasury_usdc: Account<'info, TokenAccount>,
+    pub token_program: Program<'info, Token>,
 }
 
 #[derive(Accounts)]
@@ -454,39 +2117,605 @@ pub struct EmergencyWithdraw<'info> {
     pub token_program: Program<'info, Token>,
 }
 
+#[derive(Accounts)]
+#[instruction(len: u32)]
+pub struct InitManifestStore<'info> {
+    #[account(mut)]
+    pub creator: Signer<'info>,
+    #[account(
+        seeds = [b"market", market.creator.as_ref(), market.question_hash.as_ref()],
+        bump = market.market_bump,
+        has_one = creator @ MarketError::UnauthorizedCreator
+    )]
+    pub market: Account<'info, Market>,
+    #[account(
+        init,
+        payer = creator,
+        space = 8 + ManifestStore::BASE_SIZE + len as usize,
+        seeds = [b"manifest", market.key().as_ref()],
+        bump
+    )]
+    pub manifest_store: Account<'info, ManifestStore>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct WriteManifest<'info> {
+    pub creator: Signer<'info>,
+    #[account(
+        seeds = [b"market", market.creator.as_ref(), market.question_hash.as_ref()],
+        bump = market.market_bump,
+        has_one = creator @ MarketError::UnauthorizedCreator
+    )]
+    pub market: Account<'info, Market>,
+    #[account(
+        mut,
+        seeds = [b"manifest", market.key().as_ref()],
+        bump = manifest_store.bump,
+        has_one = market
+    )]
+    pub manifest_store: Account<'info, ManifestStore>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawLmsrSurplus<'info> {
+    pub creator: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"market", market.creator.as_ref(), market.question_hash.as_ref()],
+        bump = market.market_bump,
+        has_one = creator @ MarketError::UnauthorizedCreator
+    )]
+    pub market: Account<'info, Market>,
+    #[account(mut, address = market.vault)]
+    pub vault: Account<'info, TokenAccount>,
+    #[account(mut, constraint = creator_usdc.mint == market.usdc_mint)]
+    pub creator_usdc: Account<'info, TokenAccount>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct EnableShareTokens<'info> {
+    #[account(mut)]
+    pub creator: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"market", market.creator.as_ref(), market.question_hash.as_ref()],
+        bump = market.market_bump,
+        has_one = creator @ MarketError::UnauthorizedCreator
+    )]
+    pub market: Account<'info, Market>,
+    #[account(address = market.usdc_mint)]
+    pub usdc_mint: Account<'info, Mint>,
+    // Shares are denominated like USDC so one token unit is one share unit.
+    #[account(
+        init,
+        payer = creator,
+        seeds = [b"share-mint", market.key().as_ref(), &[0]],
+        bump,
+        mint::decimals = usdc_mint.decimals,
+        mint::authority = market
+    )]
+    pub yes_mint: Account<'info, Mint>,
+    #[account(
+        init,
+        payer = creator,
+        seeds = [b"share-mint", market.key().as_ref(), &[1]],
+        bump,
+        mint::decimals = usdc_mint.decimals,
+        mint::authority = market
+    )]
+    pub no_mint: Account<'info, Mint>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateMarket<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    /// CHECK: legacy layout may not deserialize as Market; owner and discriminator are checked
+    #[account(mut, owner = crate::ID)]
+    pub market: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct MigratePosition<'info> {
+    #[account(mut)]
+    pub trader: Signer<'info>,
+    /// CHECK: market the position belongs to; only used for the PDA seeds
+    pub market: UncheckedAccount<'info>,
+    /// CHECK: legacy layout cannot deserialize as UserPosition; seeds pin it to the trader
+    #[account(
+        mut,
+        owner = crate::ID,
+        seeds = [b"user-position", market.key().as_ref(), trader.key().as_ref()],
+        bump
+    )]
+    pub user_position: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[account]
+pub struct Market {
+    pub region_id: [u8; 32],
+    pub question: String,
+    pub close_time: i64,
+    pub resolved: bool,
+    pub outcome: u8,
+    pub usdc_mint: Pubkey,
+    pub vault: Pubkey,
+    pub resolver: Pubkey,
+    pub yes_shares: u128,
+    pub no_shares: u128,
+    pub total_pool: u64,
+    pub creator: Pubkey,
+    pub market_bump: u8,
+    pub question_hash: [u8; 32],
+    pub manifest_url: String,
+    pub manifest_hash: [u8; 32],
+    // Only written by markets resolved before EvidenceRecord; see latest_evidence.
+    pub resolved_evidence_url: String,
+    pub status: ResolutionStatus,
+    pub agent_outcome: u8,
+    pub max_proof_age_slots: u64,
+    pub protocol_fee_bps: u16,
+    pub creator_fee_bps: u16,
+    pub protocol_fee_vault: Pubkey,
+    pub creator_fee_vault: Pubkey,
+    // 0 on markets created before categorical support; those are binary.
+    pub outcome_count: u8,
+    // Categorical markets only; binary markets keep using yes_shares/no_shares.
+    pub outcome_shares: [u128; MAX_OUTCOMES],
+    pub kind: MarketKind,
+    // Scalar markets only: long shares live in yes_shares, short in no_shares.
+    pub lower_bound: i64,
+    pub upper_bound: i64,
+    // Also holds a proposed scalar value while it awaits the dispute window.
+    pub resolved_value: i64,
+    // Sum of UserPosition::cost_basis; denominator for OUTCOME_INVALID refunds.
+    pub total_cost_basis: u64,
+    // Dispute settings snapshotted from DisputeConfig at creation.
+    pub arbiter: Pubkey,
+    pub dispute_window_secs: i64,
+    pub challenge_bond: u64,
+    // When the agent proposed `agent_outcome`; the window runs from here.
+    pub proposed_at: i64,
+    pub challenger: Pubkey,
+    // Default when `resolver` alone settles the market.
+    pub resolver_set: Pubkey,
+    // Zero unless the creator called require_staked_resolver.
+    pub min_resolver_stake: u64,
+    // Resolution attempts so far; the next EvidenceRecord's index.
+    pub evidence_count: u32,
+    pub latest_evidence: Pubkey,
+    // YES/NO share mints; default on markets that never enabled share tokens.
+    pub share_mints: [Pubkey; 2],
+    pub pricing_model: PricingModel,
+    // LMSR liquidity parameter in USDC base units; zero for DPM markets.
+    pub lmsr_b: u64,
+    // False on markets created before cost basis existed: their positions and
+    // total_cost_basis miss earlier trades, so OUTCOME_INVALID refunds shares.
+    pub cost_basis_tracked: bool,
+    // Set while the pending proposal came from the staked resolver and counts
+    // in its ResolverStake::open_proposals.
+    pub stake_locked: bool,
+}
+
+impl Market {
+    pub const SIZE: usize = 1500;
+
+    /// Where the market is in its lifecycle. `Closed` is derived from
+    /// `close_time` and never stored; `resolved` always means Finalized, which
+    /// also covers markets resolved before `status` was kept in sync.
+    pub fn phase(&self, now: i64) -> ResolutionStatus {
+        if self.resolved {
+            return ResolutionStatus::Finalized;
+        }
+        match self.status {
+            ResolutionStatus::Open | ResolutionStatus::Closed if now >= self.close_time => {
+                ResolutionStatus::Closed
+            }
+            status => status,
+        }
+    }
+
+    pub fn require_manifest(&self, manifest_hash: &[u8; 32]) -> Result<()> {
+        require!(
+            *manifest_hash == self.manifest_hash,
+            MarketError::ManifestHashMismatch
+        );
+        Ok(())
+    }
+
+    pub fn is_tokenized(&self) -> bool {
+        self.share_mints[0] != Pubkey::default()
+    }
+
+    /// Outcome index whose shares `mint` represents.
+    pub fn share_index(&self, mint: &Pubkey) -> Result<usize> {
+        self.share_mints
+            .iter()
+            .position(|m| m == mint && self.is_tokenized())
+            .ok_or(error!(MarketError::InvalidShareMint))
+    }
+
+    pub fn has_committee(&self) -> bool {
+        self.resolver_set != Pubkey::default()
+    }
+
+    pub fn finalize(&mut self, outcome: u8) {
+        self.outcome = outcome;
+        self.resolved = true;
+        self.status = ResolutionStatus::Finalized;
+    }
+
+    /// When the current proposal can no longer be challenged. Saturates so a
+    /// snapshotted window can never overflow the clock arithmetic.
+    pub fn dispute_ends_at(&self) -> i64 {
+        self.proposed_at.saturating_add(self.dispute_window_secs)
+    }
+
+    /// Record `outcome` as a proposal; the dispute window runs from `now`.
+    pub fn propose(&mut self, outcome: u8, now: i64) {
+        self.agent_outcome = outcome;
+        self.status = ResolutionStatus::Proposed;
+        self.proposed_at = now;
+    }
+
+    /// Resolver and creator settle directly only from Closed or an
+    /// unchallenged Disputed; proposals and challenges run their course.
+    pub fn require_direct_settlement(&self, now: i64) -> Result<()> {
+        let from = self.phase(now);
+        check_transition(from, ResolutionStatus::Finalized)?;
+        require!(from != ResolutionStatus::Proposed, MarketError::ProposalPending);
+        require!(
+            self.challenger == Pubkey::default(),
+            MarketError::ChallengePending
+        );
+        Ok(())
+    }
+
+    pub fn num_outcomes(&self) -> usize {
+        if self.is_categorical() {
+            self.outcome_count as usize
+        } else {
+            2
+        }
+    }
+
+    pub fn is_categorical(&self) -> bool {
+        self.outcome_count > 2
+    }
+
+    /// Outcome codes are the outcome index plus one; 0 is OUTCOME_NONE.
+    /// Scalar markets settle on `resolved_value` and have no outcome codes.
+    pub fn is_valid_outcome(&self, outcome: u8) -> bool {
+        self.kind != MarketKind::Scalar && outcome >= 1 && outcome as usize <= self.num_outcomes()
+    }
+
+    /// Any outcome code a resolver may settle on, including OUTCOME_INVALID.
+    pub fn is_final_outcome(&self, outcome: u8) -> bool {
+        outcome == OUTCOME_INVALID || self.is_valid_outcome(outcome)
+    }
+
+    pub fn outcome_index(&self, side: Side) -> Result<usize> {
+        let index = match side {
+            Side::Yes | Side::Long => 0,
+            Side::No | Side::Short => 1,
+            Side::Outcome(index) => index as usize,
+        };
+        require!(index < self.num_outcomes(), MarketError::InvalidOutcome);
+        Ok(index)
+    }
+
+    pub fn shares(&self, index: usize) -> u128 {
+        match (self.is_categorical(), index) {
+            (true, _) => self.outcome_shares[index],
+            (false, 0) => self.yes_shares,
+            (false, _) => self.no_shares,
+        }
+    }
+
+    pub fn total_shares(&self) -> Result<u128> {
+        (0..self.num_outcomes()).try_fold(0u128, |sum, index| {
+            sum.checked_add(self.shares(index))
+                .ok_or(error!(MarketError::MathOverflow))
+        })
+    }
+
+    pub fn set_shares(&mut self, index: usize, shares: u128) {
+        match (self.is_categorical(), index) {
+            (true, _) => self.outcome_shares[index] = shares,
+            (false, 0) => self.yes_shares = shares,
+            (false, _) => self.no_shares = shares,
+        }
+    }
+
+    pub fn all_shares(&self) -> Vec<u128> {
+        (0..self.num_outcomes()).map(|i| self.shares(i)).collect()
+    }
+
+    /// The pricing curve trades move along, at the current pool.
+    pub fn curve(&self) -> Curve {
+        match self.pricing_model {
+            PricingModel::Dpm => Curve::Dpm {
+                pool: self.total_pool as u128,
+            },
+            PricingModel::Lmsr => Curve::Lmsr { b: self.lmsr_b },
+        }
+    }
+
+    /// Fees and shares for buying `side` with `amount`; place_order fills
+    /// exactly this.
+    pub fn quote_order(&self, amount: u64, side: Side) -> Result<OrderQuote> {
+        let index = self.outcome_index(side)?;
+        let protocol_fee = fee_amount(amount, self.protocol_fee_bps)?;
+        let creator_fee = fee_amount(amount, self.creator_fee_bps)?;
+        let net_amount = amount
+            .checked_sub(protocol_fee)
+            .and_then(|v| v.checked_sub(creator_fee))
+            .ok_or(MarketError::MathUnderflow)?;
+        let curve = self.curve();
+        let mut shares = self.all_shares();
+        let shares_out = curve.shares_out(&shares, index, net_amount)?;
+        shares[index] = shares[index]
+            .checked_add(shares_out)
+            .ok_or(MarketError::MathOverflow)?;
+        Ok(OrderQuote {
+            shares_out: u64::try_from(shares_out).map_err(|_| MarketError::MathOverflow)?,
+            net_amount,
+            protocol_fee,
+            creator_fee,
+            probability_after_bps: curve.implied_probability_bps(&shares, index)?,
+        })
+    }
+
+    /// The cheapest order that buys at least `shares` of `side`: the curve's
+    /// net cost grossed up for fees. Each fee rounds down by under a unit, so
+    /// the answer lies within a few units above `(net - 2) / (1 - fees)`.
+    pub fn quote_shares(&self, side: Side, shares: u64) -> Result<SharesQuote> {
+        let index = self.outcome_index(side)?;
+        let curve = self.curve();
+        let net_amount = curve
+            .amount_in(&self.all_shares(), index, shares as u128)?
+            .max(1);
+        let keep_bps = (BPS_DENOMINATOR as u128)
+            .checked_sub(self.protocol_fee_bps as u128 + self.creator_fee_bps as u128)
+            .filter(|bps| *bps > 0)
+            .ok_or(MarketError::MathOverflow)?;
+        let net_of = |amount: u64| -> Result<u64> {
+            Ok(amount
+                - fee_amount(amount, self.protocol_fee_bps)?
+                - fee_amount(amount, self.creator_fee_bps)?)
+        };
+        let mut amount = u64::try_from(
+            net_amount.saturating_sub(2) as u128 * BPS_DENOMINATOR as u128 / keep_bps,
+        )
+        .map_err(|_| MarketError::MathOverflow)?;
+        while net_of(amount)? < net_amount {
+            amount = amount.checked_add(1).ok_or(MarketError::MathOverflow)?;
+        }
+        let order = self.quote_order(amount, side)?;
+
+        let mut after = self.all_shares();
+        after[index] += order.shares_out as u128;
+        let curve_after = match curve {
+            Curve::Dpm { pool } => Curve::Dpm {
+                pool: pool + order.net_amount as u128,
+            },
+            lmsr => lmsr,
+        };
+        Ok(SharesQuote {
+            amount,
+            shares_out: order.shares_out,
+            payout_per_share: curve_after.payout_per_share(&after, index)?,
+        })
+    }
+
+    /// USDC for selling `shares` of `side`; sell_shares pays exactly this.
+    pub fn quote_sell(&self, side: Side, shares: u64) -> Result<SellQuote> {
+        let index = self.outcome_index(side)?;
+        let curve = self.curve();
+        let mut remaining = self.all_shares();
+        let amount_out = curve.sell_proceeds(&remaining, index, shares as u128)?;
+        remaining[index] -= shares as u128;
+        Ok(SellQuote {
+            amount_out,
+            probability_after_bps: curve.implied_probability_bps(&remaining, index)?,
+        })
+    }
+}
+
+#[account]
+pub struct ProtocolConfig {
+    pub admin: Pubkey,
+    // Wallet whose USDC token account receives protocol fees.
+    pub treasury: Pubkey,
+    pub protocol_fee_bps: u16,
+    pub bump: u8,
+}
+
+impl ProtocolConfig {
+    pub const SIZE: usize = 32 + 32 + 2 + 1;
+}
+
+#[account]
+pub struct DisputeConfig {
+    pub arbiter: Pubkey,
+    pub dispute_window_secs: i64,
+    pub challenge_bond: u64,
+    pub bump: u8,
+}
+
+impl DisputeConfig {
+    pub const SIZE: usize = 32 + 8 + 8 + 1;
+}
+
+/// On-chain manifest copy: seeds ["manifest", market]. Written in chunks by
+/// the creator and immutable once sealed against `market.manifest_hash`.
+#[account]
+pub struct ManifestStore {
+    pub market: Pubkey,
+    pub sealed: bool,
+    pub bump: u8,
+    pub data: Vec<u8>,
+}
+
+impl ManifestStore {
+    // Everything but the data bytes themselves.
+    pub const BASE_SIZE: usize = 32 + 1 + 1 + 4;
+
+    pub fn write(&mut self, offset: usize, chunk: &[u8]) -> Result<()> {
+        require!(!self.sealed, MarketError::ManifestSealed);
+        let end = offset
+            .checked_add(chunk.len())
+            .filter(|end| *end <= self.data.len())
+            .ok_or(MarketError::ManifestChunkOutOfBounds)?;
+        self.data[offset..end].copy_from_slice(chunk);
+        Ok(())
+    }
+
+    pub fn seal(&mut self, manifest_hash: &[u8; 32]) -> Result<()> {
+        require!(!self.sealed, MarketError::ManifestSealed);
+        require!(
+            keccak::hash(&self.data).to_bytes() == *manifest_hash,
+            MarketError::ManifestHashMismatch
+        );
+        self.sealed = true;
+        Ok(())
+    }
+}
+
+/// Evidence a resolver relied on, as submitted with a resolution attempt.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct Evidence {
+    pub url: String,
+    // keccak256 of the fetched content the outcome was based on.
+    pub content_hash: [u8; 32],
+    pub fetched_at: i64,
+    // keccak256 of the source's domain name, e.g. "weather.gov".
+    pub source_domain_hash: [u8; 32],
+}
+
+impl Evidence {
+    pub const SIZE: usize = (4 + MAX_URL_LEN) + 32 + 8 + 32;
+}
+
+/// One per resolution attempt: seeds ["evidence", market, attempt (u32 LE)].
+#[account]
+pub struct EvidenceRecord {
+    pub market: Pubkey,
+    pub attempt: u32,
+    pub submitter: Pubkey,
+    pub outcome: u8,
+    pub evidence: Evidence,
+    pub recorded_at: i64,
+    pub bump: u8,
+}
+
+impl EvidenceRecord {
+    pub const SIZE: usize = 32 + 4 + 32 + 1 + Evidence::SIZE + 8 + 1;
+}
+
 #[account]
-pub struct Market {
-    pub region_id: [u8; 32],
-    pub question: String,
-    pub close_time: i64,
-    pub resolved: bool,
-    pub outcome: u8,
-    pub usdc_mint: Pubkey,
-    pub vault: Pubkey,
+pub struct ResolverStake {
     pub resolver: Pubkey,
-    pub yes_shares: u128,
-    pub no_shares: u128,
-    pub total_pool: u64,
-    pub creator: Pubkey,
-    pub market_bump: u8,
-    pub question_hash: [u8; 32],
-    pub manifest_url: String,
-    pub manifest_hash: [u8; 32],
-    pub resolved_evidence_url: String,
-    pub status: ResolutionStatus,
-    pub agent_outcome: u8,
+    pub vault: Pubkey,
+    // Mirrors the vault balance; reduced by withdrawals and slashing.
+    pub amount: u64,
+    // Non-zero once the resolver requested to unstake.
+    pub unlock_at: i64,
+    pub bump: u8,
+    // Proposals on staked markets still open to challenge or arbitration;
+    // the stake cannot be withdrawn until they settle.
+    pub open_proposals: u32,
 }
 
-impl Market {
-    pub const SIZE: usize = 1000;
+impl ResolverStake {
+    pub const SIZE: usize = 32 + 32 + 8 + 8 + 1 + 4;
+
+    pub fn require_active(&self, min_stake: u64) -> Result<()> {
+        require!(self.unlock_at == 0, MarketError::StakeUnlocking);
+        require!(self.amount >= min_stake, MarketError::InsufficientStake);
+        Ok(())
+    }
+
+    /// A proposal backed by this stake was finalized or arbitrated.
+    pub fn release_proposal(&mut self) {
+        self.open_proposals = self.open_proposals.saturating_sub(1);
+    }
 }
 
 #[account]
-pub struct ExternalUserLocationState {
-    pub is_verified: bool,
-    pub last_verified_slot: u64,
-    pub nullifier: [u8; 32],
-    pub region_id: [u8; 32],
+pub struct ResolverSet {
+    pub market: Pubkey,
+    pub threshold: u8,
+    pub members: Vec<Pubkey>,
+    // Parallel to `members`; OUTCOME_NONE until that member votes.
+    pub votes: Vec<ResolverVote>,
+    pub bump: u8,
+    // Both zero unless the committee votes by commit-reveal.
+    pub commit_ends_at: i64,
+    pub reveal_ends_at: i64,
+}
+
+impl ResolverSet {
+    pub const SIZE: usize =
+        32 + 1 + (4 + 32 * MAX_RESOLVERS) + (4 + ResolverVote::SIZE * MAX_RESOLVERS) + 1 + 8 + 8;
+
+    pub fn uses_commit_reveal(&self) -> bool {
+        self.reveal_ends_at != 0
+    }
+
+    /// When votes stop being accepted and `tally_votes` opens.
+    pub fn voting_ends_at(&self, close_time: i64) -> i64 {
+        if self.uses_commit_reveal() {
+            self.reveal_ends_at
+        } else {
+            close_time.saturating_add(COMMITTEE_VOTE_WINDOW_SECS)
+        }
+    }
+
+    pub fn member_index(&self, voter: &Pubkey) -> Result<usize> {
+        self.members
+            .iter()
+            .position(|m| m == voter)
+            .ok_or_else(|| error!(MarketError::UnauthorizedResolver))
+    }
+
+    pub fn has_quorum(&self, outcome: u8) -> bool {
+        self.votes.iter().filter(|v| v.outcome == outcome).count() >= self.threshold as usize
+    }
+
+    /// Outcome with the most (revealed) votes; None when nobody voted or the
+    /// lead is tied.
+    pub fn leading_outcome(&self) -> Option<u8> {
+        let count = |o: u8| self.votes.iter().filter(|v| v.outcome == o).count();
+        let revealed = || {
+            self.votes
+                .iter()
+                .map(|v| v.outcome)
+                .filter(|&o| o != OUTCOME_NONE)
+        };
+        let best = revealed().map(count).max()?;
+        let mut leaders = revealed().filter(|&o| count(o) == best);
+        let first = leaders.next()?;
+        leaders.all(|o| o == first).then_some(first)
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default)]
+pub struct ResolverVote {
+    pub outcome: u8,
+    pub evidence_hash: [u8; 32],
+    // keccak(outcome || salt); zero until committed (commit-reveal only).
+    pub commitment: [u8; 32],
+}
+
+impl ResolverVote {
+    pub const SIZE: usize = 1 + 32 + 32;
 }
 
 #[account]
@@ -495,10 +2724,38 @@ pub struct UserPosition {
     pub no_shares: u128,
     pub claimed: bool,
     pub bump: u8,
+    // Categorical markets only, indexed like Market::outcome_shares.
+    pub outcome_shares: [u128; MAX_OUTCOMES],
+    // Net USDC (after fees) this position put into the pool, less the share
+    // sold back; zero on positions opened before refunds existed.
+    pub cost_basis: u64,
 }
 
 impl UserPosition {
-    pub const SIZE: usize = 16 + 16 + 1 + 1;
+    pub const SIZE: usize = 16 + 16 + 1 + 1 + 16 * MAX_OUTCOMES + 8;
+
+    pub fn shares(&self, market: &Market, index: usize) -> u128 {
+        match (market.is_categorical(), index) {
+            (true, _) => self.outcome_shares[index],
+            (false, 0) => self.yes_shares,
+            (false, _) => self.no_shares,
+        }
+    }
+
+    pub fn total_shares(&self, market: &Market) -> Result<u128> {
+        (0..market.num_outcomes()).try_fold(0u128, |sum, index| {
+            sum.checked_add(self.shares(market, index))
+                .ok_or(error!(MarketError::MathOverflow))
+        })
+    }
+
+    pub fn set_shares(&mut self, market: &Market, index: usize, shares: u128) {
+        match (market.is_categorical(), index) {
+            (true, _) => self.outcome_shares[index] = shares,
+            (false, 0) => self.yes_shares = shares,
+            (false, _) => self.no_shares = shares,
+        }
+    }
 }
 
 #[event]
@@ -506,19 +2763,78 @@ pub struct OrderPlaced {
     pub trader: Pubkey,
     pub market: Pubkey,
     pub amount: u64,
+    pub fees: u64,
+    pub slot: u64,
+}
+
+#[event]
+pub struct SharesSold {
+    pub trader: Pubkey,
+    pub market: Pubkey,
+    pub shares: u64,
+    pub amount_out: u64,
     pub slot: u64,
 }
 
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct OrderQuote {
+    pub shares_out: u64,
+    // What enters the pool after fees.
+    pub net_amount: u64,
+    pub protocol_fee: u64,
+    pub creator_fee: u64,
+    // The side's implied probability once the order fills.
+    pub probability_after_bps: u64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct SharesQuote {
+    // What to pass to place_order, fees included.
+    pub amount: u64,
+    // What that order mints; at least the shares asked for.
+    pub shares_out: u64,
+    // What each share would pay if the side wins once the order fills, in base
+    // units × 1e18 (the LMSR always pays one unit).
+    pub payout_per_share: u128,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct SellQuote {
+    pub amount_out: u64,
+    pub probability_after_bps: u64,
+}
+
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
 pub enum Side {
     Yes,
     No,
+    // Zero-based outcome index; Yes and No are indices 0 and 1.
+    Outcome(u8),
+    // Scalar markets; aliases for indices 0 and 1.
+    Long,
+    Short,
+}
+
+// Zero-valued on legacy accounts, so existing markets price with the DPM.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum PricingModel {
+    Dpm,
+    Lmsr,
+}
+
+// Zero-valued on legacy accounts, so existing markets read as Outcomes.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum MarketKind {
+    Outcomes,
+    Scalar,
 }
 
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
 pub enum Outcome {
     Yes,
     No,
+    Index(u8),
+    Invalid,
 }
 
 #[event]
@@ -530,11 +2846,60 @@ pub struct MarketResolved {
     pub reason: String,
 }
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+#[event]
+pub struct ResolverSlashed {
+    pub market: Pubkey,
+    pub resolver: Pubkey,
+    pub to_challenger: u64,
+    pub to_treasury: u64,
+}
+
+#[event]
+pub struct VoteSubmitted {
+    pub market: Pubkey,
+    pub voter: Pubkey,
+    pub outcome: u8,
+    pub evidence_hash: [u8; 32],
+}
+
+#[event]
+pub struct VoteCommitted {
+    pub market: Pubkey,
+    pub voter: Pubkey,
+    pub commitment: [u8; 32],
+}
+
+#[event]
+pub struct ResolutionProposed {
+    pub market: Pubkey,
+    pub outcome: u8,
+    pub dispute_ends_at: i64,
+}
+
+#[event]
+pub struct ResolutionChallenged {
+    pub market: Pubkey,
+    pub challenger: Pubkey,
+    pub bond: u64,
+}
+
+#[event]
+pub struct ChallengeSettled {
+    pub market: Pubkey,
+    pub challenger: Pubkey,
+    // True when the arbiter agreed with the agent and the bond was slashed.
+    pub upheld: bool,
+    pub bond: u64,
+}
+
+// Variant order is the stored encoding; Finalized was previously `Resolved`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
 pub enum ResolutionStatus {
     Open,
     Disputed,
-    Resolved,
+    Finalized,
+    Proposed,
+    Closed,
 }
 
 #[error_code]
@@ -567,22 +2932,837 @@ pub enum MarketError {
     UnauthorizedCreator,
     #[msg("Invalid outcome")]
     InvalidOutcome,
+    #[msg("Region chain does not link the user's region")]
+    InvalidRegionChain,
+    #[msg("Location proof too old for this market")]
+    LocationProofStale,
+    #[msg("Location state does not belong to the trader")]
+    UserLocationMismatch,
+    #[msg("Fee exceeds the allowed maximum")]
+    FeeTooHigh,
+    #[msg("Unauthorized protocol admin")]
+    UnauthorizedAdmin,
+    #[msg("Fee vault account required")]
+    MissingFeeVault,
+    #[msg("Token account is not owned by the treasury")]
+    InvalidTreasury,
+    #[msg("Amount must be positive")]
+    InvalidAmount,
+    #[msg("Not enough shares")]
+    InsufficientShares,
+    #[msg("Outcome count must be between 2 and MAX_OUTCOMES")]
+    InvalidOutcomeCount,
+    #[msg("Account already uses the current layout")]
+    AlreadyMigrated,
+    #[msg("Account is not of the expected type")]
+    InvalidAccount,
+    #[msg("Scalar lower bound must be below upper bound")]
+    InvalidScalarBounds,
+    #[msg("Market is not a scalar market")]
+    NotScalarMarket,
+    #[msg("Dispute window must be between 0 and 30 days")]
+    InvalidDisputeWindow,
+    #[msg("A proposed resolution is awaiting its dispute window")]
+    ProposalPending,
+    #[msg("No proposed resolution")]
+    NotProposed,
+    #[msg("Market is not disputed")]
+    NotDisputed,
+    #[msg("Dispute window has closed")]
+    DisputeWindowClosed,
+    #[msg("Dispute window is still open")]
+    DisputeWindowOpen,
+    #[msg("Market has no arbiter")]
+    NoArbiter,
+    #[msg("Unauthorized arbiter")]
+    UnauthorizedArbiter,
+    #[msg("Challenge bond account required")]
+    MissingBondAccount,
+    #[msg("Market is still open for trading")]
+    MarketStillOpen,
+    #[msg("Illegal resolution status transition")]
+    InvalidStatusTransition,
+    #[msg("A challenge is awaiting the arbiter")]
+    ChallengePending,
+    #[msg("Market is resolved by its resolver committee")]
+    CommitteeMarket,
+    #[msg("Resolver committees are not supported for scalar markets")]
+    CommitteeUnsupported,
+    #[msg("Resolver set needs 1..=MAX_RESOLVERS distinct members and 1 <= threshold <= members")]
+    InvalidResolverSet,
+    #[msg("Resolver set account required")]
+    MissingResolverSet,
+    #[msg("Resolver already voted")]
+    AlreadyVoted,
+    #[msg("Committee votes by commit-reveal")]
+    CommitRevealRequired,
+    #[msg("Committee does not use commit-reveal")]
+    CommitRevealNotEnabled,
+    #[msg("Commit phase is over")]
+    NotCommitPhase,
+    #[msg("Not in the reveal phase")]
+    NotRevealPhase,
+    #[msg("Commitment is empty, reused or does not match the reveal")]
+    InvalidCommitment,
+    #[msg("Resolver stake below the market minimum")]
+    InsufficientStake,
+    #[msg("Resolver stake is unlocking")]
+    StakeUnlocking,
+    #[msg("Stake is still locked")]
+    StakeLocked,
+    #[msg("Resolver stake account required")]
+    MissingResolverStake,
+    #[msg("Evidence URL too long")]
+    EvidenceUrlTooLong,
+    #[msg("Reason too long")]
+    ReasonTooLong,
+    #[msg("Manifest URL too long")]
+    ManifestUrlTooLong,
+    #[msg("Manifest hash does not match the market")]
+    ManifestHashMismatch,
+    #[msg("Manifest exceeds MAX_ONCHAIN_MANIFEST_LEN")]
+    ManifestTooLarge,
+    #[msg("Manifest chunk out of bounds")]
+    ManifestChunkOutOfBounds,
+    #[msg("Manifest store is sealed")]
+    ManifestSealed,
+    #[msg("Share tokens are only available for binary markets")]
+    ShareTokensUnsupported,
+    #[msg("Market already has trades")]
+    MarketHasTrades,
+    #[msg("Share mint and token account required")]
+    MissingShareAccount,
+    #[msg("Share mint does not belong to this market")]
+    InvalidShareMint,
+    #[msg("User position account required")]
+    MissingPosition,
+    #[msg("Shares are on the losing side")]
+    LosingShares,
+    #[msg("Initial probability must be within (0, 10000) bps, or 0 for categorical markets")]
+    InvalidInitialProbability,
+    #[msg("Creator USDC and position accounts required to seed liquidity")]
+    MissingLiquidityAccounts,
+    #[msg("Not supported by this market's pricing model")]
+    UnsupportedPricingModel,
+    #[msg("LMSR liquidity parameter must be positive")]
+    InvalidLiquidityParameter,
+    #[msg("Committee voting is still open")]
+    VotingOpen,
+    #[msg("Committee voting is over")]
+    VotingClosed,
+}
+
+impl From<PricingError> for Error {
+    fn from(err: PricingError) -> Self {
+        match err {
+            PricingError::Overflow => MarketError::MathOverflow.into(),
+            PricingError::Underflow => MarketError::MathUnderflow.into(),
+        }
+    }
+}
+
+/// Legal resolution status transitions:
+/// Open → Closed → Proposed → Disputed → Finalized, where Closed may also go
+/// straight to Disputed (agent unsure) or Finalized (resolver/creator), and an
+/// unchallenged Disputed market may be proposed again.
+pub fn check_transition(from: ResolutionStatus, to: ResolutionStatus) -> Result<()> {
+    use ResolutionStatus::*;
+    match (from, to) {
+        (Finalized, _) => err!(MarketError::AlreadyResolved),
+        (Open, _) => err!(MarketError::MarketStillOpen),
+        (Closed, Proposed | Disputed | Finalized)
+        | (Proposed, Disputed | Finalized)
+        | (Disputed, Proposed | Finalized) => Ok(()),
+        _ => err!(MarketError::InvalidStatusTransition),
+    }
+}
+
+/// Commitment a resolver publishes before revealing `outcome`.
+pub fn vote_commitment(outcome: u8, salt: &[u8; 32]) -> [u8; 32] {
+    keccak::hashv(&[&[outcome], salt]).to_bytes()
 }
 
 fn question_hash(question: &str) -> [u8; 32] {
     keccak::hash(question.as_bytes()).to_bytes()
 }
 
-fn isqrt(x: u128) -> u128 {
-    // Integer sqrt via Newton's method.
-    if x == 0 {
-        return 0;
+fn fee_amount(amount: u64, fee_bps: u16) -> Result<u64> {
+    let fee = (amount as u128)
+        .checked_mul(fee_bps as u128)
+        .ok_or(MarketError::MathOverflow)?
+        / BPS_DENOMINATOR as u128;
+    u64::try_from(fee).map_err(|_| error!(MarketError::MathOverflow))
+}
+
+/// Split a resolved scalar market's pool between long and short holders by
+/// where `resolved_value` landed in [lower_bound, upper_bound].
+fn scalar_pools(market: &Market) -> Result<(u128, u128)> {
+    let range = (market.upper_bound as i128 - market.lower_bound as i128) as u128;
+    let position = (market.resolved_value as i128 - market.lower_bound as i128) as u128;
+    let pool = market.total_pool as u128;
+    let long_pool = pool
+        .checked_mul(position)
+        .ok_or(MarketError::MathOverflow)?
+        / range;
+    Ok((long_pool, pool - long_pool))
+}
+
+/// Initial shares for `liquidity` USDC of seed money. Outcome i's implied
+/// probability under the DPM is s_i² / Σ s_j², so binary (and scalar) markets
+/// put outcome 0 at `probability_bps`; categorical markets split evenly.
+/// Rounds down, keeping sqrt(Σ s_i²) at or below the deposited pool.
+fn seed_shares(liquidity: u64, probability_bps: u16, outcomes: usize) -> Result<Vec<u128>> {
+    let squared = (liquidity as u128) * (liquidity as u128);
+    if outcomes > 2 {
+        require!(probability_bps == 0, MarketError::InvalidInitialProbability);
+        return Ok(vec![isqrt(squared / outcomes as u128); outcomes]);
+    }
+    require!(
+        probability_bps > 0 && (probability_bps as u64) < BPS_DENOMINATOR,
+        MarketError::InvalidInitialProbability
+    );
+    let share = |bps: u128| -> Result<u128> {
+        Ok(isqrt(
+            squared.checked_mul(bps).ok_or(MarketError::MathOverflow)?
+                / BPS_DENOMINATOR as u128,
+        ))
+    };
+    let bps = probability_bps as u128;
+    Ok(vec![share(bps)?, share(BPS_DENOMINATOR as u128 - bps)?])
+}
+
+/// What a resolved `UserPosition` is owed (untokenized markets).
+fn position_payout(market: &Market, user_pos: &UserPosition) -> Result<u128> {
+    if market.pricing_model == PricingModel::Lmsr {
+        // Fixed payouts, scaled down only if the vault somehow fell short.
+        let owed = if market.outcome == OUTCOME_INVALID {
+            user_pos.cost_basis as u128
+        } else {
+            user_pos.shares(market, (market.outcome - 1) as usize)
+        };
+        let total = lmsr_total_claims(market)?;
+        return Ok(pro_rata(owed, total.min(market.total_pool as u128), total)?);
+    }
+    if market.outcome == OUTCOME_INVALID && !market.cost_basis_tracked {
+        // No reliable basis: refund every share equally, as share tokens are.
+        Ok(pro_rata(
+            user_pos.total_shares(market)?,
+            market.total_pool as u128,
+            market.total_shares()?,
+        )?)
+    } else if market.outcome == OUTCOME_INVALID {
+        // Void market: refund what each position put into the pool.
+        Ok(pro_rata(
+            user_pos.cost_basis as u128,
+            market.total_pool as u128,
+            market.total_cost_basis as u128,
+        )?)
+    } else if market.kind == MarketKind::Scalar {
+        // Each side's pool is paid pro rata to that side's shares.
+        let (long_pool, short_pool) = scalar_pools(market)?;
+        Ok(pro_rata(user_pos.shares(market, 0), long_pool, market.shares(0))?
+            .checked_add(pro_rata(
+                user_pos.shares(market, 1),
+                short_pool,
+                market.shares(1),
+            )?)
+            .ok_or(MarketError::MathOverflow)?)
+    } else {
+        require!(
+            market.is_valid_outcome(market.outcome),
+            MarketError::NotResolved
+        );
+        let winning_index = (market.outcome - 1) as usize;
+        let winning_total = market.shares(winning_index);
+        let user_shares = user_pos.shares(market, winning_index);
+        require!(winning_total > 0, MarketError::MathUnderflow);
+        Ok(pro_rata(user_shares, market.total_pool as u128, winning_total)?)
+    }
+}
+
+/// What `shares` tokens of outcome `index` redeem for in a resolved tokenized
+/// market. Tokens change hands without their cost basis, so a void market
+/// refunds every share token equally.
+fn share_token_payout(market: &Market, index: usize, shares: u128) -> Result<u128> {
+    let pool = market.total_pool as u128;
+    if market.outcome == OUTCOME_INVALID {
+        let total = market
+            .shares(0)
+            .checked_add(market.shares(1))
+            .ok_or(MarketError::MathOverflow)?;
+        return Ok(pro_rata(shares, pool, total)?);
+    }
+    require!(
+        market.is_valid_outcome(market.outcome),
+        MarketError::NotResolved
+    );
+    require!(
+        index == (market.outcome - 1) as usize,
+        MarketError::LosingShares
+    );
+    Ok(pro_rata(shares, pool, market.shares(index))?)
+}
+
+/// Check the optional share mint and holder account passed for outcome `index`.
+fn share_accounts<'a, 'info>(
+    market: &Market,
+    index: usize,
+    share_mint: &'a Option<Account<'info, Mint>>,
+    holder: &'a Option<Account<'info, TokenAccount>>,
+) -> Result<(&'a Account<'info, Mint>, &'a Account<'info, TokenAccount>)> {
+    let share_mint = share_mint
+        .as_ref()
+        .ok_or(MarketError::MissingShareAccount)?;
+    let holder = holder.as_ref().ok_or(MarketError::MissingShareAccount)?;
+    require_keys_eq!(
+        share_mint.key(),
+        market.share_mints[index],
+        MarketError::InvalidShareMint
+    );
+    require_keys_eq!(holder.mint, share_mint.key(), MarketError::InvalidShareMint);
+    Ok((share_mint, holder))
+}
+
+/// Settle an outcome signed by `market.resolver` alone, via
+/// `settle_or_propose`. Staked markets only accept `agent_attempt_resolution`,
+/// which checks and locks the stake.
+fn settle_as_resolver(market: &mut Account<Market>, outcome: u8, now: i64) -> Result<()> {
+    require!(market.min_resolver_stake == 0, MarketError::MissingResolverStake);
+    settle_or_propose(market, outcome, now);
+    Ok(())
+}
+
+/// Settle an outcome signed by a single party. With a dispute window it
+/// becomes a proposal open to challenge, exactly like an agent resolution.
+fn settle_or_propose(market: &mut Account<Market>, outcome: u8, now: i64) {
+    if market.dispute_window_secs == 0 {
+        market.finalize(outcome);
+        return;
+    }
+    market.propose(outcome, now);
+    emit!(ResolutionProposed {
+        market: market.key(),
+        outcome,
+        dispute_ends_at: market.dispute_ends_at(),
+    });
+}
+
+fn record_evidence(
+    record: &mut Account<EvidenceRecord>,
+    market: &mut Account<Market>,
+    submitter: Pubkey,
+    outcome: u8,
+    evidence: Evidence,
+    bump: u8,
+) -> Result<()> {
+    require!(
+        evidence.url.len() <= MAX_URL_LEN,
+        MarketError::EvidenceUrlTooLong
+    );
+    record.market = market.key();
+    record.attempt = market.evidence_count;
+    record.submitter = submitter;
+    record.outcome = outcome;
+    record.evidence = evidence;
+    record.recorded_at = Clock::get()?.unix_timestamp;
+    record.bump = bump;
+
+    market.evidence_count = market
+        .evidence_count
+        .checked_add(1)
+        .ok_or(MarketError::MathOverflow)?;
+    market.latest_evidence = record.key();
+    Ok(())
+}
+
+/// Settle a challenge's bond: back to the challenger when the proposal is
+/// overturned, into the protocol fee vault when it is upheld. A stake-backed
+/// proposal releases the resolver's stake, which is slashed if overturned.
+fn settle_challenge(accounts: &mut ArbitrateResolution, upheld: bool) -> Result<()> {
+    let market = &accounts.market;
+    let bond_vault = accounts
+        .bond_vault
+        .as_ref()
+        .ok_or(MarketError::MissingBondAccount)?;
+    let destination = if upheld {
+        accounts
+            .protocol_fee_vault
+            .as_ref()
+            .ok_or(MarketError::MissingFeeVault)?
+            .to_account_info()
+    } else {
+        accounts
+            .challenger_usdc
+            .as_ref()
+            .ok_or(MarketError::MissingBondAccount)?
+            .to_account_info()
+    };
+
+    let amount = bond_vault.amount;
+    if amount > 0 {
+        let question_hash = market.question_hash;
+        let seeds: &[&[u8]] = &[
+            b"market",
+            market.creator.as_ref(),
+            question_hash.as_ref(),
+            &[market.market_bump],
+        ];
+        let signer = &[seeds];
+        let cpi_ctx = CpiContext::new_with_signer(
+            accounts.token_program.to_account_info(),
+            Transfer {
+                from: bond_vault.to_account_info(),
+                to: destination,
+                authority: market.to_account_info(),
+            },
+            signer,
+        );
+        token::transfer(cpi_ctx, amount)?;
+    }
+
+    emit!(ChallengeSettled {
+        market: market.key(),
+        challenger: market.challenger,
+        upheld,
+        bond: amount,
+    });
+
+    if market.stake_locked {
+        if !upheld {
+            slash_resolver(accounts)?;
+        }
+        accounts
+            .resolver_stake
+            .as_mut()
+            .ok_or(MarketError::MissingResolverStake)?
+            .release_proposal();
+        accounts.market.stake_locked = false;
+    }
+    Ok(())
+}
+
+/// Slash a staked resolver whose proposal the arbiter overturned: half of
+/// RESOLVER_SLASH_BPS of its stake to the challenger, half to the protocol fee
+/// vault (and from there the treasury).
+fn slash_resolver(accounts: &mut ArbitrateResolution) -> Result<()> {
+    let stake = accounts
+        .resolver_stake
+        .as_ref()
+        .ok_or(MarketError::MissingResolverStake)?;
+    let stake_vault = accounts
+        .resolver_stake_vault
+        .as_ref()
+        .ok_or(MarketError::MissingResolverStake)?;
+    let challenger_usdc = accounts
+        .challenger_usdc
+        .as_ref()
+        .ok_or(MarketError::MissingBondAccount)?;
+    let protocol_fee_vault = accounts
+        .protocol_fee_vault
+        .as_ref()
+        .ok_or(MarketError::MissingFeeVault)?;
+
+    let slashed = fee_amount(stake.amount, RESOLVER_SLASH_BPS)?;
+    let to_challenger = slashed / 2;
+    let to_treasury = slashed - to_challenger;
+
+    let resolver = stake.resolver;
+    let seeds: &[&[u8]] = &[b"resolver-stake", resolver.as_ref(), &[stake.bump]];
+    let signer = &[seeds];
+    for (destination, amount) in [
+        (challenger_usdc.to_account_info(), to_challenger),
+        (protocol_fee_vault.to_account_info(), to_treasury),
+    ] {
+        if amount == 0 {
+            continue;
+        }
+        let cpi_ctx = CpiContext::new_with_signer(
+            accounts.token_program.to_account_info(),
+            Transfer {
+                from: stake_vault.to_account_info(),
+                to: destination,
+                authority: stake.to_account_info(),
+            },
+            signer,
+        );
+        token::transfer(cpi_ctx, amount)?;
+    }
+
+    emit!(ResolverSlashed {
+        market: accounts.market.key(),
+        resolver,
+        to_challenger,
+        to_treasury,
+    });
+    if let Some(stake) = accounts.resolver_stake.as_mut() {
+        stake.amount -= slashed;
+    }
+    Ok(())
+}
+
+/// Resize a program-owned account to `new_len`, topping up rent from `payer`.
+fn grow_account<'info>(
+    info: &AccountInfo<'info>,
+    discriminator: &[u8],
+    new_len: usize,
+    payer: &Signer<'info>,
+    system_program: &Program<'info, System>,
+) -> Result<()> {
+    require!(
+        info.try_borrow_data()?.starts_with(discriminator),
+        MarketError::InvalidAccount
+    );
+    require!(info.data_len() < new_len, MarketError::AlreadyMigrated);
+
+    let rent_due = Rent::get()?
+        .minimum_balance(new_len)
+        .saturating_sub(info.lamports());
+    if rent_due > 0 {
+        system_program::transfer(
+            CpiContext::new(
+                system_program.to_account_info(),
+                system_program::Transfer {
+                    from: payer.to_account_info(),
+                    to: info.clone(),
+                },
+            ),
+            rent_due,
+        )?;
+    }
+    info.resize(new_len)?;
+    Ok(())
+}
+
+/// Whether a user verified for `user_region` may trade in a market on `market_region`.
+/// `chain` holds registry `Region` accounts from the user's region upward, one per
+/// containment step, so a neighborhood proof can reach its city's markets.
+fn region_in_scope(
+    user_region: &[u8; 32],
+    market_region: &[u8; 32],
+    chain: &[AccountInfo],
+) -> Result<bool> {
+    if user_region == market_region {
+        return Ok(true);
+    }
+    let mut current = *user_region;
+    for info in chain.iter().take(MAX_REGION_DEPTH as usize) {
+        require_keys_eq!(
+            *info.owner,
+            ZK_LOCATION_PROGRAM_ID,
+            MarketError::InvalidRegionChain
+        );
+        let region = Region::try_deserialize(&mut &info.try_borrow_data()?[..])?;
+        require!(region.id == current, MarketError::InvalidRegionChain);
+        if region.is_root() {
+            return Ok(false);
+        }
+        current = region.parent;
+        if current == *market_region {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+/// Claim units owed across a resolved LMSR market: winning shares pay one
+/// base unit each, and OUTCOME_INVALID refunds cost basis.
+fn lmsr_total_claims(market: &Market) -> Result<u128> {
+    if market.outcome == OUTCOME_INVALID {
+        return Ok(market.total_cost_basis as u128);
+    }
+    require!(
+        market.is_valid_outcome(market.outcome),
+        MarketError::NotResolved
+    );
+    Ok(market.shares((market.outcome - 1) as usize))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ResolutionStatus::*;
+
+    #[test]
+    fn lifecycle_transitions_are_allowed() {
+        for (from, to) in [
+            (Closed, Proposed),
+            (Closed, Disputed),
+            (Closed, Finalized),
+            (Proposed, Disputed),
+            (Proposed, Finalized),
+            (Disputed, Proposed),
+            (Disputed, Finalized),
+        ] {
+            assert!(check_transition(from, to).is_ok(), "{from:?} -> {to:?}");
+        }
+    }
+
+    #[test]
+    fn finalized_markets_cannot_move() {
+        for to in [Open, Closed, Proposed, Disputed, Finalized] {
+            assert_eq!(
+                check_transition(Finalized, to),
+                Err(MarketError::AlreadyResolved.into())
+            );
+        }
+    }
+
+    #[test]
+    fn open_markets_cannot_resolve() {
+        for to in [Proposed, Disputed, Finalized] {
+            assert_eq!(
+                check_transition(Open, to),
+                Err(MarketError::MarketStillOpen.into())
+            );
+        }
+    }
+
+    #[test]
+    fn illegal_transitions_are_rejected() {
+        for (from, to) in [
+            (Closed, Open),
+            (Closed, Closed),
+            (Proposed, Proposed),
+            (Proposed, Open),
+            (Disputed, Disputed),
+            (Disputed, Closed),
+        ] {
+            assert_eq!(
+                check_transition(from, to),
+                Err(MarketError::InvalidStatusTransition.into())
+            );
+        }
+    }
+
+    fn committee(outcomes: &[u8], threshold: u8) -> ResolverSet {
+        ResolverSet {
+            market: Pubkey::default(),
+            threshold,
+            members: outcomes.iter().map(|_| Pubkey::new_unique()).collect(),
+            votes: outcomes
+                .iter()
+                .map(|&outcome| ResolverVote {
+                    outcome,
+                    ..Default::default()
+                })
+                .collect(),
+            bump: 0,
+            commit_ends_at: 1,
+            reveal_ends_at: 2,
+        }
+    }
+
+    #[test]
+    fn unrevealed_votes_abstain() {
+        let set = committee(&[OUTCOME_YES, OUTCOME_NONE, OUTCOME_NONE], 2);
+        assert_eq!(set.leading_outcome(), Some(OUTCOME_YES));
+        assert!(!set.has_quorum(OUTCOME_YES));
+
+        let set = committee(&[OUTCOME_YES, OUTCOME_NONE, OUTCOME_YES], 2);
+        assert!(set.has_quorum(OUTCOME_YES));
+    }
+
+    #[test]
+    fn tied_or_empty_committees_have_no_leader() {
+        assert_eq!(committee(&[OUTCOME_YES, OUTCOME_NO], 1).leading_outcome(), None);
+        assert_eq!(committee(&[OUTCOME_NONE, OUTCOME_NONE], 1).leading_outcome(), None);
+        assert_eq!(
+            committee(&[OUTCOME_NO, OUTCOME_YES, OUTCOME_NO], 2).leading_outcome(),
+            Some(OUTCOME_NO)
+        );
+    }
+
+    #[test]
+    fn plain_committees_stop_voting_after_the_vote_window() {
+        let mut set = committee(&[OUTCOME_YES, OUTCOME_NONE], 2);
+        assert_eq!(set.voting_ends_at(100), 2);
+        set.commit_ends_at = 0;
+        set.reveal_ends_at = 0;
+        assert_eq!(set.voting_ends_at(100), 100 + COMMITTEE_VOTE_WINDOW_SECS);
+        assert_eq!(set.voting_ends_at(i64::MAX), i64::MAX);
+    }
+
+    #[test]
+    fn commitment_binds_outcome_and_salt() {
+        let salt = [7u8; 32];
+        let commitment = vote_commitment(OUTCOME_YES, &salt);
+        assert_eq!(commitment, vote_commitment(OUTCOME_YES, &salt));
+        assert_ne!(commitment, vote_commitment(OUTCOME_NO, &salt));
+        assert_ne!(commitment, vote_commitment(OUTCOME_YES, &[8u8; 32]));
+    }
+
+    fn manifest_store(len: usize) -> ManifestStore {
+        ManifestStore {
+            market: Pubkey::default(),
+            sealed: false,
+            bump: 0,
+            data: vec![0; len],
+        }
+    }
+
+    #[test]
+    fn manifest_seals_once_chunks_match_hash() {
+        let manifest = br#"{"title":"Will it snow?"}"#;
+        let hash = keccak::hash(manifest).to_bytes();
+        let mut store = manifest_store(manifest.len());
+        store.write(10, &manifest[10..]).unwrap();
+        assert_eq!(store.seal(&hash), Err(MarketError::ManifestHashMismatch.into()));
+        store.write(0, &manifest[..10]).unwrap();
+        store.seal(&hash).unwrap();
+        assert_eq!(store.write(0, b"x"), Err(MarketError::ManifestSealed.into()));
+    }
+
+    #[test]
+    fn manifest_chunks_stay_in_bounds() {
+        let mut store = manifest_store(4);
+        assert_eq!(
+            store.write(2, b"abc"),
+            Err(MarketError::ManifestChunkOutOfBounds.into())
+        );
+        assert_eq!(
+            store.write(usize::MAX, b"a"),
+            Err(MarketError::ManifestChunkOutOfBounds.into())
+        );
+        store.write(0, b"abcd").unwrap();
+        assert_eq!(store.data, b"abcd");
+    }
+
+    #[test]
+    fn seeded_shares_price_the_starting_probability() {
+        let liquidity = 100_000_000u64;
+        let shares = seed_shares(liquidity, 7_000, 2).unwrap();
+        let sum = pricing::sum_of_squares(&shares, None).unwrap();
+        // Implied YES probability s_yes² / Σ s² lands on 70%, give or take rounding.
+        let curve = Curve::Dpm { pool: liquidity as u128 };
+        let implied_bps = curve.implied_probability_bps(&shares, 0).unwrap();
+        assert!((6_999..=7_000).contains(&implied_bps), "{implied_bps}");
+        // Rounding never credits more pool than was deposited.
+        assert!(isqrt(sum) <= liquidity as u128);
+
+        let even = seed_shares(liquidity, 0, 4).unwrap();
+        assert!(even.iter().all(|s| *s == even[0]));
+        assert!(isqrt(pricing::sum_of_squares(&even, None).unwrap()) <= liquidity as u128);
+    }
+
+    #[test]
+    fn seeded_probability_must_be_strictly_between_bounds() {
+        for bps in [0, 10_000] {
+            assert_eq!(
+                seed_shares(1_000, bps, 2),
+                Err(MarketError::InvalidInitialProbability.into())
+            );
+        }
+        assert_eq!(
+            seed_shares(1_000, 5_000, 3),
+            Err(MarketError::InvalidInitialProbability.into())
+        );
+    }
+
+    /// A zero-valued binary DPM market, as a fresh account deserializes.
+    fn binary_market() -> Market {
+        Market::deserialize(&mut &[0u8; Market::SIZE][..]).unwrap()
+    }
+
+    #[test]
+    fn quotes_match_what_trades_fill() {
+        let mut market = binary_market();
+        market.protocol_fee_bps = 100;
+        market.creator_fee_bps = 100;
+        let seeded = seed_shares(100_000_000, 5_000, 2).unwrap();
+        market.set_shares(0, seeded[0]);
+        market.set_shares(1, seeded[1]);
+        market.total_pool = 100_000_000;
+
+        let quote = market.quote_order(10_000_000, Side::Yes).unwrap();
+        assert_eq!((quote.protocol_fee, quote.creator_fee), (100_000, 100_000));
+        assert_eq!(quote.net_amount, 9_800_000);
+        let shares_out = market
+            .curve()
+            .shares_out(&market.all_shares(), 0, quote.net_amount)
+            .unwrap();
+        assert_eq!(quote.shares_out as u128, shares_out);
+        assert!(quote.probability_after_bps > 5_000);
+
+        market.set_shares(0, seeded[0] + shares_out);
+        market.total_pool += quote.net_amount;
+        let sell = market.quote_sell(Side::Yes, quote.shares_out).unwrap();
+        assert!(sell.amount_out <= quote.net_amount);
+        assert_eq!(
+            market.quote_sell(Side::No, u64::MAX),
+            Err(MarketError::MathUnderflow.into())
+        );
+    }
+
+    #[test]
+    fn share_quotes_buy_at_least_the_target() {
+        let mut market = binary_market();
+        market.protocol_fee_bps = 150;
+        market.creator_fee_bps = 75;
+        let seeded = seed_shares(100_000_000, 4_000, 2).unwrap();
+        market.set_shares(0, seeded[0]);
+        market.set_shares(1, seeded[1]);
+        market.total_pool = 100_000_000;
+
+        for target in [1, 999, 5_000_000, 40_000_000] {
+            let quote = market.quote_shares(Side::Yes, target).unwrap();
+            assert!(quote.shares_out >= target, "{target}");
+            assert_eq!(
+                market.quote_order(quote.amount, Side::Yes).unwrap().shares_out,
+                quote.shares_out
+            );
+            // One unit less no longer reaches the target, or nets nothing and
+            // place_order would reject it.
+            let short = market.quote_order(quote.amount - 1, Side::Yes).unwrap();
+            assert!(short.net_amount == 0 || short.shares_out < target, "{target}");
+            let pool = (market.total_pool
+                + market.quote_order(quote.amount, Side::Yes).unwrap().net_amount)
+                as u128;
+            assert_eq!(
+                quote.payout_per_share,
+                pool * pricing::FP_SCALE / (seeded[0] + quote.shares_out as u128)
+            );
+        }
+
+        market.pricing_model = PricingModel::Lmsr;
+        market.lmsr_b = 50_000_000;
+        let quote = market.quote_shares(Side::No, 10_000_000).unwrap();
+        assert!(quote.shares_out >= 10_000_000);
+        assert_eq!(quote.payout_per_share, pricing::FP_SCALE);
+    }
+
+    #[test]
+    fn void_markets_without_cost_basis_refund_by_shares() {
+        let mut market = binary_market();
+        market.outcome = OUTCOME_INVALID;
+        market.yes_shares = 300;
+        market.no_shares = 100;
+        market.total_pool = 1_000;
+        // A position opened before cost basis existed.
+        let mut legacy = UserPosition::deserialize(&mut &[0u8; UserPosition::SIZE][..]).unwrap();
+        legacy.yes_shares = 150;
+        legacy.no_shares = 50;
+        assert_eq!(position_payout(&market, &legacy).unwrap(), 500);
+
+        market.cost_basis_tracked = true;
+        market.total_cost_basis = 800;
+        legacy.cost_basis = 200;
+        assert_eq!(position_payout(&market, &legacy).unwrap(), 250);
     }
-    let mut z = (x + 1) >> 1;
-    let mut y = x;
-    while z < y {
-        y = z;
-        z = (x / z + z) >> 1;
+
+    #[test]
+    fn resolver_proposals_wait_out_the_dispute_window() {
+        let mut market = binary_market();
+        market.dispute_window_secs = 3_600;
+        assert!(market.require_direct_settlement(10).is_ok());
+        market.propose(OUTCOME_YES, 10);
+        assert_eq!(market.phase(3_000), ResolutionStatus::Proposed);
+        assert_eq!(market.dispute_ends_at(), 3_610);
+        market.proposed_at = i64::MAX - 1;
+        assert_eq!(market.dispute_ends_at(), i64::MAX);
+        market.proposed_at = 10;
+        assert_eq!(
+            market.require_direct_settlement(3_000),
+            Err(MarketError::ProposalPending.into())
+        );
     }
-    y
 }
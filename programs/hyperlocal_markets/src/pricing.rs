@@ -0,0 +1,584 @@
+//! Share pricing for hyperlocal markets. Kept free of Anchor so the curves can
+//! be tested on their own and mirrored by off-chain quoting.
+//!
+//! Amounts, pools and shares are USDC base units. Every rounding decision
+//! favors the market's vault: traders get at most the shares they paid for
+//! and at most the USDC their shares are worth.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PricingError {
+    Overflow,
+    Underflow,
+}
+
+pub type PricingResult<T> = core::result::Result<T, PricingError>;
+
+pub const BPS: u128 = 10_000;
+
+// Fixed-point reals: value × 10^18.
+pub const FP_SCALE: u128 = 1_000_000_000_000_000_000;
+const FP_LN_2: u128 = 693_147_180_559_945_309;
+
+/// How a market turns USDC into outcome shares.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Curve {
+    /// Square-root dynamic pari-mutuel: trades move along sqrt(Σ s_i²) and
+    /// winners split the whole `pool`. Unseeded markets start with unfunded
+    /// 1-share priors, so the pool sits below the curve until the first buy
+    /// pays for them; from then on it never falls below sqrt(Σ s_i²).
+    Dpm { pool: u128 },
+    /// Logarithmic market scoring rule, C(q) = b·ln Σ e^(q_i/b); each winning
+    /// share pays one base unit.
+    Lmsr { b: u64 },
+}
+
+impl Curve {
+    /// Shares of outcome `index` minted for `amount` (net of fees).
+    pub fn shares_out(&self, shares: &[u128], index: usize, amount: u64) -> PricingResult<u128> {
+        match *self {
+            Curve::Dpm { pool } => {
+                let new_pool = dpm_position(pool, shares)?
+                    .checked_add(amount as u128)
+                    .ok_or(PricingError::Overflow)?;
+                let others = sum_of_squares(shares, Some(index))?;
+                isqrt(
+                    new_pool
+                        .checked_mul(new_pool)
+                        .ok_or(PricingError::Overflow)?
+                        .checked_sub(others)
+                        .ok_or(PricingError::Underflow)?,
+                )
+                .checked_sub(shares[index])
+                .ok_or(PricingError::Underflow)
+            }
+            Curve::Lmsr { b } => lmsr_buy_shares(shares, b, index, amount),
+        }
+    }
+
+    /// Smallest amount (net of fees) that buys at least `target` shares of
+    /// outcome `index`.
+    pub fn amount_in(&self, shares: &[u128], index: usize, target: u128) -> PricingResult<u64> {
+        let amount = match *self {
+            Curve::Dpm { pool } => {
+                let held = shares[index]
+                    .checked_add(target)
+                    .ok_or(PricingError::Overflow)?;
+                let squared = held.checked_mul(held).ok_or(PricingError::Overflow)?;
+                let needed = isqrt_ceil(
+                    sum_of_squares(shares, Some(index))?
+                        .checked_add(squared)
+                        .ok_or(PricingError::Overflow)?,
+                );
+                needed.saturating_sub(dpm_position(pool, shares)?)
+            }
+            Curve::Lmsr { b } => lmsr_buy_cost(shares, b, index, target)?,
+        };
+        u64::try_from(amount).map_err(|_| PricingError::Overflow)
+    }
+
+    /// USDC returned for selling `sold` shares of outcome `index`.
+    pub fn sell_proceeds(&self, shares: &[u128], index: usize, sold: u128) -> PricingResult<u64> {
+        let proceeds = match *self {
+            Curve::Dpm { pool } => {
+                let mut remaining = shares.to_vec();
+                remaining[index] = remaining[index]
+                    .checked_sub(sold)
+                    .ok_or(PricingError::Underflow)?;
+                // Floor before and ceiling after keep the payout below the curve.
+                pool.min(isqrt(sum_of_squares(shares, None)?))
+                    .saturating_sub(isqrt_ceil(sum_of_squares(&remaining, None)?))
+            }
+            Curve::Lmsr { b } => {
+                if sold > shares[index] {
+                    return Err(PricingError::Underflow);
+                }
+                lmsr_sell_proceeds(shares, b, index, sold)?
+            }
+        };
+        u64::try_from(proceeds).map_err(|_| PricingError::Overflow)
+    }
+
+    /// Market-implied probability of outcome `index`, in basis points. For the
+    /// DPM that is s_i² / Σ s_j²; for the LMSR the softmax price.
+    pub fn implied_probability_bps(&self, shares: &[u128], index: usize) -> PricingResult<u64> {
+        let bps = match *self {
+            Curve::Dpm { .. } => {
+                let total = sum_of_squares(shares, None)?;
+                if total == 0 {
+                    BPS / shares.len() as u128
+                } else {
+                    let own = shares[index] * shares[index];
+                    mul_div(own, BPS, total)?
+                }
+            }
+            Curve::Lmsr { b } => {
+                let price = fp_exp_neg(lmsr_ln_price(shares, b as u128, index)?.unsigned_abs());
+                price * BPS / FP_SCALE
+            }
+        };
+        Ok(bps as u64)
+    }
+
+    /// What one share of outcome `index` would pay if it wins, in base units
+    /// × FP_SCALE: pool / s_i for the DPM, exactly one unit for the LMSR.
+    pub fn payout_per_share(&self, shares: &[u128], index: usize) -> PricingResult<u128> {
+        match *self {
+            Curve::Dpm { pool } if shares[index] == 0 => Ok(if pool == 0 { 0 } else { u128::MAX }),
+            Curve::Dpm { pool } => mul_div(pool, FP_SCALE, shares[index]),
+            Curve::Lmsr { .. } => Ok(FP_SCALE),
+        }
+    }
+}
+
+/// Where a DPM buy starts: the pool, capped at sqrt(Σ s_i²) rounded up. The
+/// pool can run a few units above the curve from rounding, and that dust stays
+/// in the vault instead of going to the next trader; below the curve (the
+/// unfunded priors) the buyer pays the difference.
+fn dpm_position(pool: u128, shares: &[u128]) -> PricingResult<u128> {
+    Ok(pool.min(isqrt_ceil(sum_of_squares(shares, None)?)))
+}
+
+/// `shares / total_shares` of `pool`, rounded down; zero when nobody holds shares.
+pub fn pro_rata(shares: u128, pool: u128, total_shares: u128) -> PricingResult<u128> {
+    if total_shares == 0 {
+        return Ok(0);
+    }
+    mul_div(shares, pool, total_shares)
+}
+
+fn mul_div(a: u128, b: u128, denominator: u128) -> PricingResult<u128> {
+    Ok(a.checked_mul(b).ok_or(PricingError::Overflow)? / denominator)
+}
+
+/// Sum of squared outcome shares, optionally leaving one outcome out.
+pub fn sum_of_squares(shares: &[u128], skip: Option<usize>) -> PricingResult<u128> {
+    let mut total: u128 = 0;
+    for (i, s) in shares.iter().enumerate() {
+        if Some(i) == skip {
+            continue;
+        }
+        total = s
+            .checked_mul(*s)
+            .and_then(|sq| total.checked_add(sq))
+            .ok_or(PricingError::Overflow)?;
+    }
+    Ok(total)
+}
+
+pub fn isqrt(x: u128) -> u128 {
+    // Integer sqrt via Newton's method.
+    if x == 0 {
+        return 0;
+    }
+    let mut z = (x + 1) >> 1;
+    let mut y = x;
+    while z < y {
+        y = z;
+        z = (x / z + z) >> 1;
+    }
+    y
+}
+
+pub fn isqrt_ceil(x: u128) -> u128 {
+    let r = isqrt(x);
+    if r * r == x {
+        r
+    } else {
+        r + 1
+    }
+}
+
+/// e^(−x) for x ≥ 0: e^(−x) = 2^(−k)·e^(−r) with r in [0, ln 2), where the
+/// Taylor series for e^(−r) converges within a few dozen terms.
+pub fn fp_exp_neg(x: u128) -> u128 {
+    let k = x / FP_LN_2;
+    if k >= 64 {
+        return 0;
+    }
+    let r = x - k * FP_LN_2;
+    let mut term = FP_SCALE;
+    let mut sum = FP_SCALE as i128;
+    for n in 1..=40u128 {
+        term = term * r / FP_SCALE / n;
+        if term == 0 {
+            break;
+        }
+        if n % 2 == 1 {
+            sum -= term as i128;
+        } else {
+            sum += term as i128;
+        }
+    }
+    (sum as u128) >> k
+}
+
+/// ln(v) for v > 0: v = m·2^k with m in [1, 2), and ln m = 2·atanh((m−1)/(m+1)).
+pub fn fp_ln(v: u128) -> i128 {
+    debug_assert!(v > 0);
+    let mut m = v;
+    let mut k: i128 = 0;
+    while m >= 2 * FP_SCALE {
+        m >>= 1;
+        k += 1;
+    }
+    while m < FP_SCALE {
+        m <<= 1;
+        k -= 1;
+    }
+    let z = (m - FP_SCALE) * FP_SCALE / (m + FP_SCALE);
+    let z2 = z * z / FP_SCALE;
+    let mut power = z;
+    let mut sum = z;
+    for n in 1..=40u128 {
+        power = power * z2 / FP_SCALE;
+        if power == 0 {
+            break;
+        }
+        sum += power / (2 * n + 1);
+    }
+    k * FP_LN_2 as i128 + 2 * sum as i128
+}
+
+/// `value × b / FP_SCALE` as base units, rounded down.
+fn fp_mul_units(value: u128, b: u128) -> PricingResult<u128> {
+    mul_div(value, b, FP_SCALE)
+}
+
+/// ln p_i (≤ 0) for outcome `index` of an LMSR with outstanding `shares`,
+/// where p_i = e^(q_i/b) / Σ e^(q_j/b). Exponents are taken relative to the
+/// largest q so every term stays in (0, 1].
+fn lmsr_ln_price(shares: &[u128], b: u128, index: usize) -> PricingResult<i128> {
+    let top = shares.iter().copied().max().unwrap_or(0);
+    let gap = mul_div(top - shares[index], FP_SCALE, b)?;
+    Ok(-(gap as i128) - lmsr_ln_sum(shares, b, top)?)
+}
+
+/// ln Σ e^((q_j − top)/b) for `top` ≥ every q_j, so C(q) = top + b·(this).
+fn lmsr_ln_sum(shares: &[u128], b: u128, top: u128) -> PricingResult<i128> {
+    let mut sum: u128 = 0;
+    for q in shares {
+        sum += fp_exp_neg(mul_div(top - q, FP_SCALE, b)?);
+    }
+    Ok(fp_ln(sum.max(1)))
+}
+
+/// C(after) − C(before) in base units × FP_SCALE. Each side is measured from
+/// its own largest q, keeping both log-sums at or above zero where fixed point
+/// is accurate, even when one outcome's price is within rounding of 0 or 1.
+fn lmsr_cost_change(before: &[u128], after: &[u128], b: u128) -> PricingResult<i128> {
+    let top = |q: &[u128]| q.iter().copied().max().unwrap_or(0);
+    let (top_before, top_after) = (top(before), top(after));
+    let tops = (top_after as i128 - top_before as i128)
+        .checked_mul(FP_SCALE as i128)
+        .ok_or(PricingError::Overflow)?;
+    let sums = lmsr_ln_sum(after, b, top_after)? - lmsr_ln_sum(before, b, top_before)?;
+    sums.checked_mul(b as i128)
+        .and_then(|sums| sums.checked_add(tops))
+        .ok_or(PricingError::Overflow)
+}
+
+/// Shares of outcome `index` that `amount` buys: solving
+/// C(q + Δ·e_i) − C(q) = amount with C(q) = b·ln Σ e^(q_j/b) gives
+/// Δ/b = ln(1 + (e^(a/b) − 1)/p_i) = a/b + ln(1 − e^(−a/b)(1 − p_i)) − ln p_i.
+/// One base unit of slack absorbs fixed-point error in the vault's favor, and
+/// Newton steps down the cost curve trim any Δ that would still cost more.
+fn lmsr_buy_shares(shares: &[u128], b: u64, index: usize, amount: u64) -> PricingResult<u128> {
+    let b = b as u128;
+    let x = (amount as u128) * FP_SCALE / b;
+    let ln_p = lmsr_ln_price(shares, b, index)?;
+    let p = fp_exp_neg(ln_p.unsigned_abs());
+    let inner = (FP_SCALE - fp_exp_neg(x) * (FP_SCALE - p) / FP_SCALE).max(1);
+    let delta = (x as i128 + fp_ln(inner) - ln_p).max(0) as u128;
+    let mut minted = fp_mul_units(delta, b)?.saturating_sub(1);
+    let budget = (amount as i128) * FP_SCALE as i128;
+    let mut after = shares.to_vec();
+    loop {
+        after[index] = shares[index]
+            .checked_add(minted)
+            .ok_or(PricingError::Overflow)?;
+        let excess = lmsr_cost_change(shares, &after, b)? - budget;
+        if excess <= 0 || minted == 0 {
+            return Ok(minted);
+        }
+        // Cost is convex in Δ, so stepping back along the tangent never
+        // undershoots the affordable Δ by more than the rounding.
+        let price = fp_exp_neg(lmsr_ln_price(&after, b, index)?.unsigned_abs()).max(1);
+        let cut = (excess as u128).div_ceil(price);
+        minted = minted.saturating_sub(cut.max(1));
+    }
+}
+
+/// Cost of `target` more shares of outcome `index`, C(q + Δ·e_i) − C(q),
+/// rounded up with two base units of slack so the shares it buys always
+/// reach `target`.
+fn lmsr_buy_cost(shares: &[u128], b: u64, index: usize, target: u128) -> PricingResult<u128> {
+    let mut after = shares.to_vec();
+    after[index] = after[index]
+        .checked_add(target)
+        .ok_or(PricingError::Overflow)?;
+    let cost = lmsr_cost_change(shares, &after, b as u128)?.max(0) as u128;
+    Ok(cost.div_ceil(FP_SCALE) + 2)
+}
+
+/// USDC returned for selling `sold` shares of outcome `index`, C(q) − C(q − Δ·e_i).
+/// Never more than one unit per share and rounded down with the same unit of
+/// slack as buys.
+fn lmsr_sell_proceeds(shares: &[u128], b: u64, index: usize, sold: u128) -> PricingResult<u128> {
+    let mut remaining = shares.to_vec();
+    remaining[index] -= sold;
+    let proceeds = lmsr_cost_change(&remaining, shares, b as u128)?.max(0) as u128 / FP_SCALE;
+    Ok(proceeds.min(sold).saturating_sub(1))
+}
+
+/// Most an LMSR market maker can lose: b·ln(n), rounded up plus a unit of slack.
+pub fn lmsr_max_loss(b: u64, outcomes: usize) -> PricingResult<u64> {
+    let loss = fp_mul_units(fp_ln(outcomes as u128 * FP_SCALE) as u128, b as u128)?;
+    u64::try_from(loss + 2).map_err(|_| PricingError::Overflow)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    #[test]
+    fn fixed_point_exp_and_ln_match_f64() {
+        for x in [0.0, 0.1, 0.5, 1.0, 2.5, 10.0, 30.0] {
+            let got = fp_exp_neg((x * 1e18) as u128) as f64 / 1e18;
+            assert!((got - f64::exp(-x)).abs() < 1e-12, "e^-{x} = {got}");
+        }
+        for v in [1e-9, 0.3, 1.0, 2.0, 7.9, 1e6] {
+            let got = fp_ln((v * 1e18) as u128) as f64 / 1e18;
+            assert!((got - f64::ln(v)).abs() < 1e-9, "ln {v} = {got}");
+        }
+    }
+
+    #[test]
+    fn lmsr_buy_matches_the_cost_function() {
+        let b = 100_000_000f64;
+        let cost = |q: &[f64]| b * q.iter().map(|q| (q / b).exp()).sum::<f64>().ln();
+        let curve = Curve::Lmsr { b: b as u64 };
+        let bought = curve.shares_out(&[30_000_000, 0], 1, 20_000_000).unwrap() as f64;
+        let paid = cost(&[30_000_000.0, bought]) - cost(&[30_000_000.0, 0.0]);
+        assert!((paid - 20_000_000.0).abs() < 2.0, "{paid}");
+    }
+
+    #[test]
+    fn lmsr_max_loss_covers_one_sided_buying() {
+        let b = 100_000_000u64;
+        let curve = Curve::Lmsr { b };
+        let subsidy = lmsr_max_loss(b, 2).unwrap();
+        assert!(subsidy as f64 >= b as f64 * 2f64.ln());
+        let mut shares = vec![0u128, 0];
+        let mut pool = subsidy as u128;
+        for _ in 0..50 {
+            shares[0] += curve.shares_out(&shares, 0, 50_000_000).unwrap();
+            pool += 50_000_000;
+            assert!(pool >= shares[0]);
+        }
+    }
+
+    #[test]
+    fn empty_dpm_prices_outcomes_evenly() {
+        let curve = Curve::Dpm { pool: 0 };
+        assert_eq!(curve.implied_probability_bps(&[0, 0, 0, 0], 2), Ok(2_500));
+    }
+
+    #[test]
+    fn payout_per_share_splits_the_pool_among_winners() {
+        let curve = Curve::Dpm { pool: 3_000_000 };
+        assert_eq!(
+            curve.payout_per_share(&[1_000_000, 2_000_000], 0),
+            Ok(3 * FP_SCALE)
+        );
+        assert_eq!(
+            curve.payout_per_share(&[1_000_000, 2_000_000], 1),
+            Ok(3 * FP_SCALE / 2)
+        );
+        assert_eq!(curve.payout_per_share(&[1_000_000, 0], 1), Ok(u128::MAX));
+        assert_eq!(Curve::Dpm { pool: 0 }.payout_per_share(&[0, 0], 0), Ok(0));
+        assert_eq!(
+            Curve::Lmsr { b: 1_000 }.payout_per_share(&[5, 0], 0),
+            Ok(FP_SCALE)
+        );
+    }
+
+    #[test]
+    fn first_dpm_buy_pays_for_the_priors() {
+        let curve = Curve::Dpm { pool: 0 };
+        let minted = curve.shares_out(&[1, 1], 0, 1_000_000).unwrap();
+        assert_eq!(minted, 999_998);
+        assert!(1_000_000 * 1_000_000 >= sum_of_squares(&[1 + minted, 1], None).unwrap());
+        assert_eq!(
+            curve.shares_out(&[1; 8], 0, 2),
+            Err(PricingError::Underflow)
+        );
+    }
+
+    // Smallest buy that mints a share on a fresh market of up to 8 outcomes.
+    const MIN_DPM_BUY: u64 = 3;
+
+    /// A DPM market in a reachable state: either fresh, with unfunded 1-share
+    /// priors and an empty pool, or traded, with `pool` covering sqrt(Σ s²) as
+    /// it does after any sequence of floor-rounded buys.
+    fn dpm_market() -> impl Strategy<Value = (Curve, Vec<u128>)> {
+        let fresh = (2usize..=8).prop_map(|n| (Curve::Dpm { pool: 0 }, vec![1u128; n]));
+        let traded = (
+            prop::collection::vec(1u128..1_000_000_000_000, 2..=8),
+            0u128..1_000_000,
+        )
+            .prop_map(|(shares, slack)| {
+                let pool = isqrt_ceil(sum_of_squares(&shares, None).unwrap()) + slack;
+                (Curve::Dpm { pool }, shares)
+            });
+        prop_oneof![1 => fresh, 4 => traded]
+    }
+
+    fn lmsr_market() -> impl Strategy<Value = (Curve, Vec<u128>)> {
+        (
+            1_000_000u64..1_000_000_000_000,
+            prop::collection::vec(0u128..1_000_000_000_000, 2..=8),
+        )
+            .prop_map(|(b, shares)| (Curve::Lmsr { b }, shares))
+    }
+
+    fn any_market() -> impl Strategy<Value = (Curve, Vec<u128>)> {
+        prop_oneof![dpm_market(), lmsr_market()]
+    }
+
+    fn bought(curve: Curve, shares: &[u128], index: usize, amount: u64) -> (Curve, Vec<u128>) {
+        let minted = curve.shares_out(shares, index, amount).unwrap();
+        let mut after = shares.to_vec();
+        after[index] += minted;
+        let curve = match curve {
+            Curve::Dpm { pool } => Curve::Dpm {
+                pool: pool + amount as u128,
+            },
+            lmsr => lmsr,
+        };
+        (curve, after)
+    }
+
+    proptest! {
+        #[test]
+        fn more_money_never_buys_fewer_shares(
+            (curve, shares) in any_market(),
+            index in 0usize..8,
+            amount in MIN_DPM_BUY..100_000_000_000,
+            extra in 0u64..100_000_000_000,
+        ) {
+            let index = index % shares.len();
+            let small = curve.shares_out(&shares, index, amount).unwrap();
+            let large = curve.shares_out(&shares, index, amount + extra).unwrap();
+            prop_assert!(small <= large);
+        }
+
+        #[test]
+        fn buying_raises_the_price(
+            (curve, shares) in any_market(),
+            index in 0usize..8,
+            amount in 1_000_000u64..100_000_000_000,
+        ) {
+            let index = index % shares.len();
+            let before = curve.implied_probability_bps(&shares, index).unwrap();
+            let (curve_after, after) = bought(curve, &shares, index, amount);
+            prop_assert!(curve_after.implied_probability_bps(&after, index).unwrap() >= before);
+        }
+
+        #[test]
+        fn buy_then_sell_never_creates_value(
+            (curve, shares) in any_market(),
+            index in 0usize..8,
+            amount in MIN_DPM_BUY..100_000_000_000,
+        ) {
+            let index = index % shares.len();
+            let minted = curve.shares_out(&shares, index, amount).unwrap();
+            let (curve_after, after) = bought(curve, &shares, index, amount);
+            let back = curve_after.sell_proceeds(&after, index, minted).unwrap();
+            prop_assert!(back <= amount, "paid {} got back {}", amount, back);
+        }
+
+        #[test]
+        fn shares_never_cost_less_than_quoted(
+            (curve, shares) in any_market(),
+            index in 0usize..8,
+            target in 1u128..10_000_000_000,
+        ) {
+            let index = index % shares.len();
+            let amount = curve.amount_in(&shares, index, target).unwrap();
+            prop_assert!(curve.shares_out(&shares, index, amount).unwrap() >= target);
+            if amount > 0 {
+                // Any less and the target is out of reach; LMSR quotes carry slack.
+                let short = curve.shares_out(&shares, index, amount - 1).unwrap();
+                if let Curve::Dpm { .. } = curve {
+                    prop_assert!(short < target);
+                }
+            }
+        }
+
+        #[test]
+        fn lmsr_shares_never_cost_more_than_a_unit(
+            (curve, shares) in lmsr_market(),
+            index in 0usize..8,
+            amount in 0u64..100_000_000_000,
+        ) {
+            let index = index % shares.len();
+            let minted = curve.shares_out(&shares, index, amount).unwrap();
+            // Flooring to whole units plus the unit of slack costs at most two.
+            prop_assert!(minted + 2 >= amount as u128);
+        }
+
+        #[test]
+        fn dpm_pool_always_covers_the_curve(
+            (curve, shares) in dpm_market(),
+            index in 0usize..8,
+            amount in MIN_DPM_BUY..100_000_000_000,
+            sell_bps in 0u128..=10_000,
+        ) {
+            let index = index % shares.len();
+            let (curve, mut shares) = bought(curve, &shares, index, amount);
+            let Curve::Dpm { pool } = curve else { unreachable!() };
+            prop_assert!(pool * pool >= sum_of_squares(&shares, None).unwrap());
+
+            let sold = shares[index] * sell_bps / BPS;
+            let out = curve.sell_proceeds(&shares, index, sold).unwrap() as u128;
+            shares[index] -= sold;
+            let pool = pool - out;
+            prop_assert!(pool * pool >= sum_of_squares(&shares, None).unwrap());
+        }
+
+        #[test]
+        fn lmsr_pool_covers_every_outcome(
+            b in 1_000_000u64..1_000_000_000_000,
+            outcomes in 2usize..=8,
+            trades in prop::collection::vec((0usize..8, 0u64..10_000_000_000, any::<bool>()), 1..20),
+        ) {
+            let curve = Curve::Lmsr { b };
+            let mut shares = vec![0u128; outcomes];
+            let mut pool = lmsr_max_loss(b, outcomes).unwrap() as u128;
+            for (index, amount, buy) in trades {
+                let index = index % outcomes;
+                if buy {
+                    shares[index] += curve.shares_out(&shares, index, amount).unwrap();
+                    pool += amount as u128;
+                } else {
+                    let sold = shares[index].min(amount as u128);
+                    pool -= curve.sell_proceeds(&shares, index, sold).unwrap() as u128;
+                    shares[index] -= sold;
+                }
+                prop_assert!(shares.iter().all(|q| *q <= pool), "pool {} shares {:?}", pool, shares);
+            }
+        }
+
+        #[test]
+        fn probabilities_sum_to_one(
+            (curve, shares) in any_market(),
+        ) {
+            let total: u64 = (0..shares.len())
+                .map(|i| curve.implied_probability_bps(&shares, i).unwrap())
+                .sum();
+            // Each term rounds down by less than one basis point.
+            prop_assert!(total <= BPS as u64 && total + shares.len() as u64 >= BPS as u64);
+        }
+    }
+}
@@ -31,10 +31,10 @@ async function createMockUserLocation({
   regionId: Buffer;
 }) {
   const [pda, bump] = anchor.web3.PublicKey.findProgramAddressSync(
-    [Buffer.from(USER_STATE_SEED), user.toBuffer()],
+    [Buffer.from(USER_STATE_SEED), user.toBuffer(), regionId],
     ZK_LOCATION_PROGRAM_ID
   );
-  const data = Buffer.alloc(8 + 1 + 8 + 32 + 32); // disc + bool + u64 + nullifier + region
+  const data = Buffer.alloc(8 + 1 + 8 + 32 + 32 + 1); // disc + bool + u64 + nullifier + region + version
   // Discriminator for "account:UserLocationState"
   const name = "account:UserLocationState";
   Buffer.from(anchor.utils.sha256.hash(name)).copy(data, 0, 0, 8);
@@ -42,6 +42,7 @@ async function createMockUserLocation({
   data.writeBigUInt64LE(BigInt(0), 9); // last_verified_slot
   // nullifier left zeroed at offset 17..49
   regionId.copy(data, 9 + 8 + 32); // region at offset 49
+  data.writeUInt8(1, 9 + 8 + 32 + 32); // version = USER_STATE_VERSION
   // nullifier left as zeros by default
 
   const tx = new anchor.web3.Transaction().add(
@@ -127,7 +128,7 @@ describe("hyperlocal_markets dpm", () => {
     const vaultAta = await getAssociatedTokenAddress(mint, marketPda, true);
 
     const [userLocPda] = anchor.web3.PublicKey.findProgramAddressSync(
-      [Buffer.from(USER_STATE_SEED), user.publicKey.toBuffer()],
+      [Buffer.from(USER_STATE_SEED), user.publicKey.toBuffer(), regionId],
       ZK_LOCATION_PROGRAM_ID
     );
     await createMockUserLocation({
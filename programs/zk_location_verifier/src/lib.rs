@@ -1,17 +1,217 @@
 use anchor_lang::prelude::*;
-use groth16_solana::groth16::Groth16Verifier;
+use anchor_lang::system_program;
+use groth16_solana::groth16::{Groth16Verifier, Groth16Verifyingkey};
 
-pub mod verifying_key;
+pub mod region;
+
+pub use region::{
+    bbox_contains, bbox_values, lat_input, lon_input, polygon_region_id, radius_region_id,
+    region_id, wallet_input,
+};
 
 declare_id!("56qEvUYQnhfEf557ftLVdjtwqfJA6TpG2cnru3MyhT7Z");
 
+// Upper bound on circuit public inputs a stored verifying key may describe.
+pub const MAX_PUBLIC_INPUTS: usize = 8;
+// Current UserLocationState layout / region_id scheme.
+pub const USER_STATE_VERSION: u8 = 1;
+pub const MAX_REGION_NAME_LEN: usize = 64;
+// Root regions sit at depth 0; bounds how far markets walk the containment chain.
+pub const MAX_REGION_DEPTH: u8 = 4;
+// Vertex count the polygon circuit is compiled for.
+pub const MAX_POLYGON_VERTICES: u8 = 16;
+
 #[program]
 pub mod zk_location_verifier {
     use super::*;
 
-    pub fn init_config(ctx: Context<InitConfig>) -> Result<()> {
+    /// Create the Config PDA. Only the program's upgrade authority may call this,
+    /// and it becomes the config admin.
+    pub fn init_config(ctx: Context<InitConfig>, verifying_key: StoredVerifyingKey) -> Result<()> {
+        verifying_key.validate()?;
         let config = &mut ctx.accounts.config;
+        config.admin = ctx.accounts.payer.key();
         config.bump = ctx.bumps.config;
+        config.vk_version = 1;
+        config.verifying_key = verifying_key;
+        emit!(VerifyingKeyUpdated {
+            admin: config.admin,
+            vk_version: config.vk_version,
+        });
+        Ok(())
+    }
+
+    /// Upgrade a Config created before it held the admin and verifying key (a
+    /// lone bump byte) in place. Gated on the upgrade authority like init_config.
+    pub fn migrate_config(
+        ctx: Context<MigrateConfig>,
+        verifying_key: StoredVerifyingKey,
+    ) -> Result<()> {
+        verifying_key.validate()?;
+        let info = ctx.accounts.config.to_account_info();
+        require!(
+            info.data_len() == 8 + Config::LEGACY_SIZE,
+            ZkLocationError::AlreadyMigrated
+        );
+
+        let new_len = 8 + Config::SIZE;
+        let rent_due = Rent::get()?
+            .minimum_balance(new_len)
+            .saturating_sub(info.lamports());
+        if rent_due > 0 {
+            system_program::transfer(
+                CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    system_program::Transfer {
+                        from: ctx.accounts.payer.to_account_info(),
+                        to: info.clone(),
+                    },
+                ),
+                rent_due,
+            )?;
+        }
+        info.resize(new_len)?;
+
+        let config = Config {
+            admin: ctx.accounts.payer.key(),
+            bump: ctx.bumps.config,
+            vk_version: 1,
+            verifying_key,
+        };
+        let mut data = info.try_borrow_mut_data()?;
+        let mut out: &mut [u8] = &mut data[8..];
+        config.serialize(&mut out)?;
+        emit!(VerifyingKeyUpdated {
+            admin: config.admin,
+            vk_version: config.vk_version,
+        });
+        Ok(())
+    }
+
+    /// Replace the stored verifying key after the circuit's zkey is regenerated.
+    pub fn set_verifying_key(
+        ctx: Context<SetVerifyingKey>,
+        verifying_key: StoredVerifyingKey,
+    ) -> Result<()> {
+        verifying_key.validate()?;
+        let config = &mut ctx.accounts.config;
+        config.vk_version = config
+            .vk_version
+            .checked_add(1)
+            .ok_or(ZkLocationError::VersionOverflow)?;
+        config.verifying_key = verifying_key;
+        emit!(VerifyingKeyUpdated {
+            admin: config.admin,
+            vk_version: config.vk_version,
+        });
+        Ok(())
+    }
+
+    /// Register a named bounding-box region, optionally nested inside a parent
+    /// region whose box fully contains it.
+    pub fn register_region(
+        ctx: Context<RegisterRegion>,
+        name: String,
+        min_lat: [u8; 32],
+        max_lat: [u8; 32],
+        min_lon: [u8; 32],
+        max_lon: [u8; 32],
+    ) -> Result<()> {
+        require!(
+            name.len() <= MAX_REGION_NAME_LEN,
+            ZkLocationError::RegionNameTooLong
+        );
+        let bounds = [min_lat, max_lat, min_lon, max_lon];
+        require!(
+            bbox_values(&bounds).is_some(),
+            ZkLocationError::InvalidRegionBounds
+        );
+
+        let (parent, depth) = match &ctx.accounts.parent {
+            Some(parent) => {
+                require!(
+                    bbox_contains(&parent.bounds(), &bounds),
+                    ZkLocationError::RegionNotContained
+                );
+                require!(parent.depth < MAX_REGION_DEPTH, ZkLocationError::RegionTooDeep);
+                (parent.id, parent.depth + 1)
+            }
+            None => ([0u8; 32], 0),
+        };
+
+        let region = &mut ctx.accounts.region;
+        region.id = region_id(&min_lat, &max_lat, &min_lon, &max_lon);
+        region.parent = parent;
+        region.depth = depth;
+        region.min_lat = min_lat;
+        region.max_lat = max_lat;
+        region.min_lon = min_lon;
+        region.max_lon = max_lon;
+        region.name = name;
+        region.authority = ctx.accounts.authority.key();
+        region.bump = ctx.bumps.region;
+
+        emit!(RegionRegistered {
+            region_id: region.id,
+            parent,
+            name: region.name.clone(),
+        });
+        Ok(())
+    }
+
+    /// Permissionless: move a region under a tighter registered box that contains
+    /// it. Roots can always be nested, so nobody can squat a box as a root and
+    /// cut it off from its real hierarchy. A nested region only moves down to a
+    /// sibling, a child of its current parent, so nobody can pull it out of its
+    /// hierarchy through a root of their own; boxes only shrink, which rules out
+    /// cycles. Passing the current parent again refreshes a depth left stale by
+    /// an ancestor's move.
+    pub fn reparent_region(ctx: Context<ReparentRegion>) -> Result<()> {
+        check_reparent(&ctx.accounts.region, &ctx.accounts.new_parent)?;
+
+        let new_parent = &ctx.accounts.new_parent;
+        let region = &mut ctx.accounts.region;
+        region.parent = new_parent.id;
+        region.depth = new_parent.depth + 1;
+        emit!(RegionReparented {
+            region_id: region.id,
+            parent: region.parent,
+            depth: region.depth,
+        });
+        Ok(())
+    }
+
+    /// Register a polygon geofence by its vertex commitment. The vertex list itself
+    /// is published off-chain (e.g. in the market manifest); polygons are always
+    /// root regions since containment cannot be checked against a commitment.
+    pub fn register_polygon_region(
+        ctx: Context<RegisterPolygonRegion>,
+        name: String,
+        commitment: [u8; 32],
+        vertex_count: u8,
+    ) -> Result<()> {
+        require!(
+            name.len() <= MAX_REGION_NAME_LEN,
+            ZkLocationError::RegionNameTooLong
+        );
+        require!(
+            (3..=MAX_POLYGON_VERTICES).contains(&vertex_count),
+            ZkLocationError::InvalidPolygon
+        );
+
+        let region = &mut ctx.accounts.region;
+        region.id = polygon_region_id(&commitment);
+        region.commitment = commitment;
+        region.vertex_count = vertex_count;
+        region.name = name;
+        region.authority = ctx.accounts.authority.key();
+        region.bump = ctx.bumps.region;
+
+        emit!(RegionRegistered {
+            region_id: region.id,
+            parent: [0u8; 32],
+            name: region.name.clone(),
+        });
         Ok(())
     }
 
@@ -20,35 +220,174 @@ pub mod zk_location_verifier {
         proof: Groth16Proof,
         public_inputs: LocationPublicInputs,
     ) -> Result<()> {
-        // Verify Groth16 proof against the embedded verifying key.
-        let vk = verifying_key::verifying_key();
-        let (proof_a, proof_b, proof_c) = proof.flatten();
-        let pub_inputs = public_inputs.as_public_inputs();
-        let mut verifier = Groth16Verifier::new(&proof_a, &proof_b, &proof_c, &pub_inputs, &vk)
-            .map_err(|_| error!(ZkLocationError::InvalidProof))?;
-        verifier.verify().map_err(|_| error!(ZkLocationError::InvalidProof))?;
-
-        // Compute region_id = keccak(targetLat || targetLon || radiusSq).
-        let mut region_seed = Vec::with_capacity(96);
-        region_seed.extend_from_slice(&public_inputs.min_lat);
-        region_seed.extend_from_slice(&public_inputs.max_lat);
-        region_seed.extend_from_slice(&public_inputs.min_lon);
-        region_seed.extend_from_slice(&public_inputs.max_lon);
-        let mut region_id = [0u8; 32];
-        for (i, byte) in region_seed.iter().enumerate().take(32) {
-            region_id[i] = region_seed[i]
-                ^ public_inputs.max_lat[i]
-                ^ public_inputs.min_lon[i]
-                ^ public_inputs.max_lon[i];
-        }
+        // Verify Groth16 proof against the verifying key stored in Config.
+        verify_groth16(
+            &ctx.accounts.config.verifying_key,
+            &proof,
+            &public_inputs.as_public_inputs(&wallet_input(&ctx.accounts.signer.key())),
+        )?;
+
+        let clock = Clock::get()?;
+        claim_nullifier(
+            &mut ctx.accounts.nullifier_record,
+            ctx.accounts.signer.key(),
+            clock.epoch,
+            ctx.bumps.nullifier_record,
+        )?;
 
-        // Persist membership.
+        let region_id = region_id(
+            &public_inputs.min_lat,
+            &public_inputs.max_lat,
+            &public_inputs.min_lon,
+            &public_inputs.max_lon,
+        );
+        ctx.accounts.user_state.record_proof(
+            region_id,
+            public_inputs.location_nullifier,
+            clock.slot,
+        );
+        Ok(())
+    }
+
+    /// Store the verifying key for a non-bounding-box circuit in its own slot.
+    pub fn set_circuit_verifying_key(
+        ctx: Context<SetCircuitVerifyingKey>,
+        kind: CircuitKind,
+        verifying_key: StoredVerifyingKey,
+    ) -> Result<()> {
+        verifying_key.validate()?;
+        let slot = &mut ctx.accounts.vk_slot;
+        slot.kind = kind;
+        slot.bump = ctx.bumps.vk_slot;
+        slot.vk_version = slot
+            .vk_version
+            .checked_add(1)
+            .ok_or(ZkLocationError::VersionOverflow)?;
+        slot.verifying_key = verifying_key;
+        emit!(VerifyingKeyUpdated {
+            admin: ctx.accounts.admin.key(),
+            vk_version: slot.vk_version,
+        });
+        Ok(())
+    }
+
+    /// Prove membership in a circular geofence (venue-scale markets).
+    pub fn submit_radius_proof(
+        ctx: Context<SubmitRadiusProof>,
+        proof: Groth16Proof,
+        public_inputs: RadiusPublicInputs,
+    ) -> Result<()> {
+        verify_groth16(
+            &ctx.accounts.vk_slot.verifying_key,
+            &proof,
+            &public_inputs.as_public_inputs(&wallet_input(&ctx.accounts.signer.key())),
+        )?;
+
+        let clock = Clock::get()?;
+        claim_nullifier(
+            &mut ctx.accounts.nullifier_record,
+            ctx.accounts.signer.key(),
+            clock.epoch,
+            ctx.bumps.nullifier_record,
+        )?;
+
+        let region_id = radius_region_id(
+            &public_inputs.target_lat,
+            &public_inputs.target_lon,
+            &public_inputs.radius_sq,
+        );
+        ctx.accounts.user_state.record_proof(
+            region_id,
+            public_inputs.location_nullifier,
+            clock.slot,
+        );
+        Ok(())
+    }
+
+    /// Prove membership in a registered polygon region (wards, districts).
+    pub fn submit_polygon_proof(
+        ctx: Context<SubmitPolygonProof>,
+        proof: Groth16Proof,
+        public_inputs: PolygonPublicInputs,
+    ) -> Result<()> {
+        verify_groth16(
+            &ctx.accounts.vk_slot.verifying_key,
+            &proof,
+            &public_inputs.as_public_inputs(&wallet_input(&ctx.accounts.signer.key())),
+        )?;
+
+        let clock = Clock::get()?;
+        claim_nullifier(
+            &mut ctx.accounts.nullifier_record,
+            ctx.accounts.signer.key(),
+            clock.epoch,
+            ctx.bumps.nullifier_record,
+        )?;
+
+        let region_id = ctx.accounts.polygon_region.id;
+        ctx.accounts.user_state.record_proof(
+            region_id,
+            public_inputs.location_nullifier,
+            clock.slot,
+        );
+        Ok(())
+    }
+
+    /// Let a user withdraw their own verification, e.g. after leaving the region.
+    pub fn revoke_location(ctx: Context<RevokeLocation>) -> Result<()> {
         let user_state = &mut ctx.accounts.user_state;
-        user_state.is_verified = true;
-    user_state.last_verified_slot = Clock::get()?.slot;
-    user_state.nullifier = [0u8; 32];
-        user_state.region_id = region_id;
+        require!(
+            UserLocationState::is_address_for(
+                &user_state.key(),
+                &ctx.accounts.owner.key(),
+                &user_state.region_id
+            ),
+            ZkLocationError::UserStateMismatch
+        );
+        user_state.is_verified = false;
+        emit!(LocationRevoked {
+            user: ctx.accounts.owner.key(),
+            region_id: user_state.region_id,
+        });
+        Ok(())
+    }
+
+    /// Upgrade a pre-versioning UserLocationState in place. Its region_id came from
+    /// the retired XOR derivation, so verification is cleared and the owner must
+    /// submit a fresh proof.
+    pub fn migrate_user_state(ctx: Context<MigrateUserState>) -> Result<()> {
+        let info = ctx.accounts.user_state.to_account_info();
+        require!(
+            info.data_len() == 8 + UserLocationState::LEGACY_SIZE,
+            ZkLocationError::AlreadyMigrated
+        );
+
+        let new_len = 8 + UserLocationState::SIZE;
+        let rent_due = Rent::get()?
+            .minimum_balance(new_len)
+            .saturating_sub(info.lamports());
+        if rent_due > 0 {
+            system_program::transfer(
+                CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    system_program::Transfer {
+                        from: ctx.accounts.owner.to_account_info(),
+                        to: info.clone(),
+                    },
+                ),
+                rent_due,
+            )?;
+        }
+        info.resize(new_len)?;
 
+        let mut data = info.try_borrow_mut_data()?;
+        let mut legacy: &[u8] = &data[8..8 + UserLocationState::LEGACY_SIZE];
+        let mut state = UserLocationState::deserialize(&mut legacy)?;
+        state.is_verified = false;
+        state.region_id = [0u8; 32];
+        state.version = USER_STATE_VERSION;
+        let mut out: &mut [u8] = &mut data[8..];
+        state.serialize(&mut out)?;
         Ok(())
     }
 }
@@ -65,33 +404,289 @@ pub struct InitConfig<'info> {
         bump
     )]
     pub config: Account<'info, Config>,
+    #[account(constraint = program.programdata_address()? == Some(program_data.key()))]
+    pub program: Program<'info, crate::program::ZkLocationVerifier>,
+    #[account(
+        constraint = program_data.upgrade_authority_address == Some(payer.key())
+            @ ZkLocationError::UnauthorizedAdmin
+    )]
+    pub program_data: Account<'info, ProgramData>,
     pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
+pub struct MigrateConfig<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    /// CHECK: legacy layout cannot deserialize as Config; seeds and owner pin it
+    #[account(mut, owner = crate::ID, seeds = [b"config"], bump)]
+    pub config: UncheckedAccount<'info>,
+    #[account(constraint = program.programdata_address()? == Some(program_data.key()))]
+    pub program: Program<'info, crate::program::ZkLocationVerifier>,
+    #[account(
+        constraint = program_data.upgrade_authority_address == Some(payer.key())
+            @ ZkLocationError::UnauthorizedAdmin
+    )]
+    pub program_data: Account<'info, ProgramData>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetVerifyingKey<'info> {
+    pub admin: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = admin @ ZkLocationError::UnauthorizedAdmin
+    )]
+    pub config: Account<'info, Config>,
+}
+
+#[derive(Accounts)]
+#[instruction(proof: Groth16Proof, public_inputs: LocationPublicInputs)]
 pub struct SubmitProof<'info> {
     #[account(mut)]
     pub signer: Signer<'info>,
 
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        init_if_needed,
+        payer = signer,
+        space = 8 + UserLocationState::SIZE,
+        seeds = [
+            b"user-state",
+            signer.key().as_ref(),
+            region_id(
+                &public_inputs.min_lat,
+                &public_inputs.max_lat,
+                &public_inputs.min_lon,
+                &public_inputs.max_lon,
+            )
+            .as_ref(),
+        ],
+        bump
+    )]
+    pub user_state: Account<'info, UserLocationState>,
+
+    #[account(
+        init_if_needed,
+        payer = signer,
+        space = 8 + NullifierRecord::SIZE,
+        seeds = [b"nullifier", public_inputs.location_nullifier.as_ref()],
+        bump
+    )]
+    pub nullifier_record: Account<'info, NullifierRecord>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(name: String, min_lat: [u8; 32], max_lat: [u8; 32], min_lon: [u8; 32], max_lon: [u8; 32])]
+pub struct RegisterRegion<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + Region::SIZE,
+        seeds = [b"region", region_id(&min_lat, &max_lat, &min_lon, &max_lon).as_ref()],
+        bump
+    )]
+    pub region: Account<'info, Region>,
+    #[account(seeds = [b"region", parent.id.as_ref()], bump = parent.bump)]
+    pub parent: Option<Account<'info, Region>>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ReparentRegion<'info> {
+    #[account(mut, seeds = [b"region", region.id.as_ref()], bump = region.bump)]
+    pub region: Account<'info, Region>,
+    #[account(seeds = [b"region", new_parent.id.as_ref()], bump = new_parent.bump)]
+    pub new_parent: Account<'info, Region>,
+}
+
+#[derive(Accounts)]
+#[instruction(kind: CircuitKind)]
+pub struct SetCircuitVerifyingKey<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = admin @ ZkLocationError::UnauthorizedAdmin
+    )]
+    pub config: Account<'info, Config>,
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = 8 + CircuitVerifyingKey::SIZE,
+        seeds = [b"vk", kind.seed()],
+        bump
+    )]
+    pub vk_slot: Account<'info, CircuitVerifyingKey>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(proof: Groth16Proof, public_inputs: RadiusPublicInputs)]
+pub struct SubmitRadiusProof<'info> {
+    #[account(mut)]
+    pub signer: Signer<'info>,
+
+    #[account(seeds = [b"vk", CircuitKind::Radius.seed()], bump = vk_slot.bump)]
+    pub vk_slot: Account<'info, CircuitVerifyingKey>,
+
+    #[account(
+        init_if_needed,
+        payer = signer,
+        space = 8 + UserLocationState::SIZE,
+        seeds = [
+            b"user-state",
+            signer.key().as_ref(),
+            radius_region_id(
+                &public_inputs.target_lat,
+                &public_inputs.target_lon,
+                &public_inputs.radius_sq,
+            )
+            .as_ref(),
+        ],
+        bump
+    )]
+    pub user_state: Account<'info, UserLocationState>,
+
+    #[account(
+        init_if_needed,
+        payer = signer,
+        space = 8 + NullifierRecord::SIZE,
+        seeds = [b"nullifier", public_inputs.location_nullifier.as_ref()],
+        bump
+    )]
+    pub nullifier_record: Account<'info, NullifierRecord>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(name: String, commitment: [u8; 32])]
+pub struct RegisterPolygonRegion<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + PolygonRegion::SIZE,
+        seeds = [b"region", polygon_region_id(&commitment).as_ref()],
+        bump
+    )]
+    pub region: Account<'info, PolygonRegion>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(proof: Groth16Proof, public_inputs: PolygonPublicInputs)]
+pub struct SubmitPolygonProof<'info> {
+    #[account(mut)]
+    pub signer: Signer<'info>,
+
+    #[account(seeds = [b"vk", CircuitKind::Polygon.seed()], bump = vk_slot.bump)]
+    pub vk_slot: Account<'info, CircuitVerifyingKey>,
+
+    #[account(
+        seeds = [b"region", polygon_region_id(&public_inputs.polygon_commitment).as_ref()],
+        bump = polygon_region.bump
+    )]
+    pub polygon_region: Account<'info, PolygonRegion>,
+
     #[account(
         init_if_needed,
         payer = signer,
         space = 8 + UserLocationState::SIZE,
-        seeds = [b"user-state", signer.key().as_ref()],
+        seeds = [b"user-state", signer.key().as_ref(), polygon_region.id.as_ref()],
         bump
     )]
     pub user_state: Account<'info, UserLocationState>,
 
+    #[account(
+        init_if_needed,
+        payer = signer,
+        space = 8 + NullifierRecord::SIZE,
+        seeds = [b"nullifier", public_inputs.location_nullifier.as_ref()],
+        bump
+    )]
+    pub nullifier_record: Account<'info, NullifierRecord>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RevokeLocation<'info> {
+    pub owner: Signer<'info>,
+    // Either the per-region PDA or the legacy single-region one; checked in the handler.
+    #[account(mut)]
+    pub user_state: Account<'info, UserLocationState>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateUserState<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    /// CHECK: legacy layout cannot deserialize as UserLocationState; seeds pin it to the owner
+    #[account(
+        mut,
+        owner = crate::ID,
+        seeds = [b"user-state", owner.key().as_ref()],
+        bump
+    )]
+    pub user_state: UncheckedAccount<'info>,
     pub system_program: Program<'info, System>,
 }
 
 #[account]
 pub struct Config {
+    pub admin: Pubkey,
     pub bump: u8,
+    // Bumped on every key rotation so provers can tell which zkey is live.
+    pub vk_version: u32,
+    pub verifying_key: StoredVerifyingKey,
 }
 
 impl Config {
-    pub const SIZE: usize = 1;
+    pub const SIZE: usize = 32 + 1 + 4 + StoredVerifyingKey::SIZE;
+    // Layout before the admin and verifying key were stored: just the bump.
+    pub const LEGACY_SIZE: usize = 1;
+}
+
+#[account]
+pub struct CircuitVerifyingKey {
+    pub kind: CircuitKind,
+    pub bump: u8,
+    pub vk_version: u32,
+    pub verifying_key: StoredVerifyingKey,
+}
+
+impl CircuitVerifyingKey {
+    pub const SIZE: usize = 1 + 1 + 4 + StoredVerifyingKey::SIZE;
+}
+
+/// Circuits whose verifying keys live in `["vk", kind]` slots. The bounding-box
+/// circuit predates the slots and keeps its key in `Config`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum CircuitKind {
+    Radius,
+    Polygon,
+}
+
+impl CircuitKind {
+    pub fn seed(&self) -> &'static [u8] {
+        match self {
+            CircuitKind::Radius => b"radius",
+            CircuitKind::Polygon => b"polygon",
+        }
+    }
 }
 
 #[account]
@@ -100,10 +695,124 @@ pub struct UserLocationState {
     pub last_verified_slot: u64,
     pub nullifier: [u8; 32],
     pub region_id: [u8; 32],
+    pub version: u8,
 }
 
 impl UserLocationState {
-    pub const SIZE: usize = 1 + 8 + 32 + 32;
+    pub const SIZE: usize = 1 + 8 + 32 + 32 + 1;
+    // Layout before `version` was appended.
+    pub const LEGACY_SIZE: usize = 1 + 8 + 32 + 32;
+
+    pub fn record_proof(&mut self, region_id: [u8; 32], nullifier: [u8; 32], slot: u64) {
+        self.is_verified = true;
+        self.last_verified_slot = slot;
+        self.nullifier = nullifier;
+        self.region_id = region_id;
+        self.version = USER_STATE_VERSION;
+    }
+
+    /// PDA holding a user's proof for one region: `["user-state", user, region_id]`.
+    pub fn address(user: &Pubkey, region_id: &[u8; 32]) -> Pubkey {
+        Pubkey::find_program_address(&[b"user-state", user.as_ref(), region_id], &crate::ID).0
+    }
+
+    /// Single-region PDA used before per-region states: `["user-state", user]`.
+    pub fn legacy_address(user: &Pubkey) -> Pubkey {
+        Pubkey::find_program_address(&[b"user-state", user.as_ref()], &crate::ID).0
+    }
+
+    /// Whether `key` is `user`'s state account for `region_id`, in either layout.
+    pub fn is_address_for(key: &Pubkey, user: &Pubkey, region_id: &[u8; 32]) -> bool {
+        *key == Self::address(user, region_id) || *key == Self::legacy_address(user)
+    }
+}
+
+#[account]
+pub struct Region {
+    pub id: [u8; 32],
+    // Zeroed for root regions.
+    pub parent: [u8; 32],
+    pub depth: u8,
+    pub min_lat: [u8; 32],
+    pub max_lat: [u8; 32],
+    pub min_lon: [u8; 32],
+    pub max_lon: [u8; 32],
+    pub name: String,
+    pub authority: Pubkey,
+    pub bump: u8,
+}
+
+impl Region {
+    pub const SIZE: usize = 32 + 32 + 1 + 32 * 4 + (4 + MAX_REGION_NAME_LEN) + 32 + 1;
+
+    pub fn bounds(&self) -> [[u8; 32]; 4] {
+        [self.min_lat, self.max_lat, self.min_lon, self.max_lon]
+    }
+
+    pub fn is_root(&self) -> bool {
+        self.parent == [0u8; 32]
+    }
+}
+
+#[account]
+pub struct PolygonRegion {
+    pub id: [u8; 32],
+    pub commitment: [u8; 32],
+    pub vertex_count: u8,
+    pub name: String,
+    pub authority: Pubkey,
+    pub bump: u8,
+}
+
+impl PolygonRegion {
+    pub const SIZE: usize = 32 + 32 + 1 + (4 + MAX_REGION_NAME_LEN) + 32 + 1;
+}
+
+#[account]
+pub struct NullifierRecord {
+    pub owner: Pubkey,
+    pub epoch: u64,
+    pub bump: u8,
+}
+
+impl NullifierRecord {
+    pub const SIZE: usize = 32 + 8 + 1;
+}
+
+/// Groth16 verifying key in groth16-solana byte layout (big-endian limbs,
+/// G2 points ordered `[x1, x0, y1, y0]`).
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct StoredVerifyingKey {
+    pub nr_pubinputs: u8,
+    pub alpha_g1: [u8; 64],
+    pub beta_g2: [u8; 128],
+    pub gamma_g2: [u8; 128],
+    pub delta_g2: [u8; 128],
+    pub ic: Vec<[u8; 64]>,
+}
+
+impl StoredVerifyingKey {
+    pub const SIZE: usize = 1 + 64 + 128 + 128 + 128 + 4 + 64 * (MAX_PUBLIC_INPUTS + 1);
+
+    pub fn validate(&self) -> Result<()> {
+        require!(
+            (self.nr_pubinputs as usize) <= MAX_PUBLIC_INPUTS
+                && self.ic.len() == self.nr_pubinputs as usize + 1,
+            ZkLocationError::InvalidVerifyingKey
+        );
+        Ok(())
+    }
+
+    pub fn as_groth16(&self) -> Groth16Verifyingkey<'_> {
+        Groth16Verifyingkey {
+            nr_pubinputs: self.nr_pubinputs as usize,
+            vk_alpha_g1: self.alpha_g1,
+            vk_beta_g2: self.beta_g2,
+            vk_gamme_g2: self.gamma_g2,
+            vk_delta_g2: self.delta_g2,
+            vk_ic: &self.ic,
+        }
+    }
 }
 
 #[derive(AnchorSerialize, AnchorDeserialize, Clone)]
@@ -113,8 +822,12 @@ pub struct Groth16Proof {
     pub proof_c: [[u8; 32]; 2],
 }
 
+// Field order follows the circuit's public signals: outputs first, then inputs.
+// Every circuit's last public input is the prover's wallet (see `wallet_input`);
+// the program derives it from the signer rather than taking it from the caller.
 #[derive(AnchorSerialize, AnchorDeserialize, Clone)]
 pub struct LocationPublicInputs {
+    pub location_nullifier: [u8; 32],
     pub min_lat: [u8; 32],
     pub max_lat: [u8; 32],
     pub min_lon: [u8; 32],
@@ -122,16 +835,52 @@ pub struct LocationPublicInputs {
 }
 
 impl LocationPublicInputs {
-    pub fn as_public_inputs(&self) -> [[u8; 32]; 4] {
+    pub fn as_public_inputs(&self, wallet: &[u8; 32]) -> [[u8; 32]; 6] {
         [
+            self.location_nullifier,
             self.min_lat,
             self.max_lat,
             self.min_lon,
             self.max_lon,
+            *wallet,
+        ]
+    }
+}
+
+// Field order follows the radius circuit's public signals.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct RadiusPublicInputs {
+    pub location_nullifier: [u8; 32],
+    pub target_lat: [u8; 32],
+    pub target_lon: [u8; 32],
+    pub radius_sq: [u8; 32],
+}
+
+impl RadiusPublicInputs {
+    pub fn as_public_inputs(&self, wallet: &[u8; 32]) -> [[u8; 32]; 5] {
+        [
+            self.location_nullifier,
+            self.target_lat,
+            self.target_lon,
+            self.radius_sq,
+            *wallet,
         ]
     }
 }
 
+// Field order follows the polygon circuit's public signals.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct PolygonPublicInputs {
+    pub location_nullifier: [u8; 32],
+    pub polygon_commitment: [u8; 32],
+}
+
+impl PolygonPublicInputs {
+    pub fn as_public_inputs(&self, wallet: &[u8; 32]) -> [[u8; 32]; 3] {
+        [self.location_nullifier, self.polygon_commitment, *wallet]
+    }
+}
+
 impl Groth16Proof {
     pub fn flatten(&self) -> ([u8; 64], [u8; 128], [u8; 64]) {
         let mut a = [0u8; 64];
@@ -152,8 +901,292 @@ impl Groth16Proof {
     }
 }
 
+fn verify_groth16<const N: usize>(
+    stored_vk: &StoredVerifyingKey,
+    proof: &Groth16Proof,
+    public_inputs: &[[u8; 32]; N],
+) -> Result<()> {
+    require!(
+        stored_vk.nr_pubinputs as usize == N,
+        ZkLocationError::VerifyingKeyMismatch
+    );
+    let vk = stored_vk.as_groth16();
+    let (proof_a, proof_b, proof_c) = proof.flatten();
+    let mut verifier = Groth16Verifier::new(&proof_a, &proof_b, &proof_c, public_inputs, &vk)
+        .map_err(|_| error!(ZkLocationError::InvalidProof))?;
+    verifier.verify().map_err(|_| error!(ZkLocationError::InvalidProof))?;
+    Ok(())
+}
+
+fn check_reparent(region: &Region, new_parent: &Region) -> Result<()> {
+    require!(
+        new_parent.id != region.id && bbox_contains(&new_parent.bounds(), &region.bounds()),
+        ZkLocationError::RegionNotContained
+    );
+    require!(new_parent.depth < MAX_REGION_DEPTH, ZkLocationError::RegionTooDeep);
+    if !region.is_root() && new_parent.id != region.parent {
+        require!(
+            new_parent.parent == region.parent,
+            ZkLocationError::ParentNotTighter
+        );
+    }
+    Ok(())
+}
+
+// A nullifier may only back one wallet per epoch. That alone would let whoever
+// lands a copied proof first lock the real prover out, so proofs also commit to
+// the signer through the wallet public input and don't verify for anyone else.
+fn claim_nullifier(
+    record: &mut NullifierRecord,
+    signer: Pubkey,
+    epoch: u64,
+    bump: u8,
+) -> Result<()> {
+    if record.owner != Pubkey::default() && record.epoch == epoch {
+        require_keys_eq!(record.owner, signer, ZkLocationError::NullifierAlreadyUsed);
+    }
+    record.owner = signer;
+    record.epoch = epoch;
+    record.bump = bump;
+    Ok(())
+}
+
+#[event]
+pub struct VerifyingKeyUpdated {
+    pub admin: Pubkey,
+    pub vk_version: u32,
+}
+
+#[event]
+pub struct RegionRegistered {
+    pub region_id: [u8; 32],
+    pub parent: [u8; 32],
+    pub name: String,
+}
+
+#[event]
+pub struct RegionReparented {
+    pub region_id: [u8; 32],
+    pub parent: [u8; 32],
+    pub depth: u8,
+}
+
+#[event]
+pub struct LocationRevoked {
+    pub user: Pubkey,
+    pub region_id: [u8; 32],
+}
+
 #[error_code]
 pub enum ZkLocationError {
     #[msg("Invalid Groth16 proof")]
     InvalidProof,
+    #[msg("Malformed verifying key")]
+    InvalidVerifyingKey,
+    #[msg("Verifying key does not match proof public inputs")]
+    VerifyingKeyMismatch,
+    #[msg("Unauthorized config admin")]
+    UnauthorizedAdmin,
+    #[msg("Verifying key version overflow")]
+    VersionOverflow,
+    #[msg("Location nullifier already used by another wallet this epoch")]
+    NullifierAlreadyUsed,
+    #[msg("Account already uses the current layout")]
+    AlreadyMigrated,
+    #[msg("Region name too long")]
+    RegionNameTooLong,
+    #[msg("Region bounds are inverted or outside the coordinate encoding")]
+    InvalidRegionBounds,
+    #[msg("Region is not contained in its parent")]
+    RegionNotContained,
+    #[msg("Region hierarchy too deep")]
+    RegionTooDeep,
+    // No longer raised since reparent_region stopped taking the current parent;
+    // kept so later error codes don't shift.
+    #[msg("Current parent region missing or wrong")]
+    MissingCurrentParent,
+    #[msg("New parent must be a child of the current parent")]
+    ParentNotTighter,
+    #[msg("User state account does not belong to this user")]
+    UserStateMismatch,
+    #[msg("Polygon must have between 3 and 16 vertices")]
+    InvalidPolygon,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::region::{MAX_LAT_INPUT, MAX_LON_INPUT};
+
+    // Bounds in degrees, encoded as the prover would.
+    fn bbox(min_lat: f64, max_lat: f64, min_lon: f64, max_lon: f64) -> [[u8; 32]; 4] {
+        let micro = |deg: f64| (deg * 1e6).round() as i64;
+        [
+            lat_input(micro(min_lat)).unwrap(),
+            lat_input(micro(max_lat)).unwrap(),
+            lon_input(micro(min_lon)).unwrap(),
+            lon_input(micro(max_lon)).unwrap(),
+        ]
+    }
+
+    #[test]
+    fn coordinate_inputs_are_offset_and_bounded() {
+        let mut limb = [0u8; 32];
+        assert_eq!(lat_input(-90_000_000), Some(limb));
+        limb[24..].copy_from_slice(&90_000_000u64.to_be_bytes());
+        assert_eq!(lat_input(0), Some(limb));
+        assert!(lat_input(90_000_000).is_some());
+        assert!(lat_input(90_000_001).is_none());
+        assert!(lat_input(-90_000_001).is_none());
+        assert!(lon_input(-180_000_000).is_some());
+        assert!(lon_input(180_000_001).is_none());
+    }
+
+    #[test]
+    fn region_id_is_deterministic_and_shape_separated() {
+        let [a, b, c, d] = bbox(-1.0, 1.0, -2.0, 2.0);
+        assert_eq!(region_id(&a, &b, &c, &d), region_id(&a, &b, &c, &d));
+        assert_ne!(region_id(&a, &b, &c, &d), region_id(&c, &d, &a, &b));
+        assert_ne!(region_id(&a, &b, &c, &d)[..], radius_region_id(&a, &b, &c)[..]);
+    }
+
+    #[test]
+    fn boxes_straddling_the_equator_and_meridian_are_valid() {
+        assert!(bbox_values(&bbox(-1.0, 1.0, -1.0, 1.0)).is_some());
+        assert!(bbox_values(&bbox(-90.0, 90.0, -180.0, 180.0)).is_some());
+        assert!(bbox_values(&bbox(1.0, -1.0, -1.0, 1.0)).is_none());
+        assert!(bbox_values(&bbox(-1.0, 1.0, 1.0, -1.0)).is_none());
+    }
+
+    #[test]
+    fn unencoded_or_out_of_range_bounds_are_rejected() {
+        let mut bounds = bbox(-1.0, 1.0, -1.0, 1.0);
+        // A raw negative coordinate is p - x, far above any encoded value.
+        bounds[0] = [0xff; 32];
+        assert!(bbox_values(&bounds).is_none());
+
+        let mut bounds = bbox(-1.0, 1.0, -1.0, 1.0);
+        bounds[1][24..].copy_from_slice(&(MAX_LAT_INPUT + 1).to_be_bytes());
+        assert!(bbox_values(&bounds).is_none());
+
+        let mut bounds = bbox(-1.0, 1.0, -1.0, 1.0);
+        bounds[3][24..].copy_from_slice(&(MAX_LON_INPUT + 1).to_be_bytes());
+        assert!(bbox_values(&bounds).is_none());
+    }
+
+    #[test]
+    fn containment_works_across_zero() {
+        let world = bbox(-90.0, 90.0, -180.0, 180.0);
+        let gulf_of_guinea = bbox(-5.0, 5.0, -5.0, 5.0);
+        let south_west = bbox(-4.0, -1.0, -4.0, -1.0);
+        let straddle = bbox(-1.0, 1.0, -1.0, 1.0);
+        let london = bbox(51.3, 51.7, -0.5, 0.3);
+
+        assert!(bbox_contains(&world, &gulf_of_guinea));
+        assert!(bbox_contains(&gulf_of_guinea, &south_west));
+        assert!(bbox_contains(&gulf_of_guinea, &straddle));
+        assert!(bbox_contains(&gulf_of_guinea, &gulf_of_guinea));
+        assert!(bbox_contains(&world, &london));
+        assert!(!bbox_contains(&south_west, &straddle));
+        assert!(!bbox_contains(&gulf_of_guinea, &london));
+        assert!(!bbox_contains(&straddle, &gulf_of_guinea));
+    }
+
+    #[test]
+    fn invalid_boxes_contain_nothing() {
+        let world = bbox(-90.0, 90.0, -180.0, 180.0);
+        let mut raw = bbox(-1.0, 1.0, -1.0, 1.0);
+        raw[0] = [0xff; 32];
+        assert!(!bbox_contains(&world, &raw));
+        assert!(!bbox_contains(&raw, &world));
+    }
+
+    fn region(bounds: [[u8; 32]; 4], parent: Option<&Region>) -> Region {
+        let [min_lat, max_lat, min_lon, max_lon] = bounds;
+        Region {
+            id: region_id(&min_lat, &max_lat, &min_lon, &max_lon),
+            parent: parent.map_or([0u8; 32], |p| p.id),
+            depth: parent.map_or(0, |p| p.depth + 1),
+            min_lat,
+            max_lat,
+            min_lon,
+            max_lon,
+            name: String::new(),
+            authority: Pubkey::default(),
+            bump: 0,
+        }
+    }
+
+    #[test]
+    fn nested_regions_only_move_to_siblings() {
+        let city = region(bbox(40.0, 41.0, -75.0, -73.0), None);
+        let borough = region(bbox(40.5, 40.9, -74.1, -73.7), Some(&city));
+        let block = region(bbox(40.7, 40.8, -74.0, -73.9), Some(&city));
+        assert!(check_reparent(&block, &borough).is_ok());
+        // Refreshing the depth under the current parent is always allowed.
+        assert!(check_reparent(&block, &city).is_ok());
+
+        // A root squatted inside the city can't adopt the block.
+        let squat = region(bbox(40.6, 40.85, -74.05, -73.8), None);
+        assert_eq!(
+            check_reparent(&block, &squat),
+            Err(ZkLocationError::ParentNotTighter.into())
+        );
+        // Nor can a grandchild of the city.
+        let precinct = region(bbox(40.65, 40.85, -74.05, -73.85), Some(&borough));
+        assert_eq!(
+            check_reparent(&block, &precinct),
+            Err(ZkLocationError::ParentNotTighter.into())
+        );
+    }
+
+    #[test]
+    fn roots_nest_under_any_containing_box() {
+        let city = region(bbox(40.0, 41.0, -75.0, -73.0), None);
+        let block = region(bbox(40.7, 40.8, -74.0, -73.9), None);
+        assert!(check_reparent(&block, &city).is_ok());
+        assert_eq!(
+            check_reparent(&city, &block),
+            Err(ZkLocationError::RegionNotContained.into())
+        );
+        assert_eq!(
+            check_reparent(&city, &city),
+            Err(ZkLocationError::RegionNotContained.into())
+        );
+    }
+
+    fn record() -> NullifierRecord {
+        NullifierRecord {
+            owner: Pubkey::default(),
+            epoch: 0,
+            bump: 0,
+        }
+    }
+
+    #[test]
+    fn nullifier_claims_once_per_epoch() {
+        let alice = Pubkey::new_unique();
+        let bob = Pubkey::new_unique();
+        let mut nullifier = record();
+
+        assert!(claim_nullifier(&mut nullifier, alice, 7, 254).is_ok());
+        assert_eq!((nullifier.owner, nullifier.epoch, nullifier.bump), (alice, 7, 254));
+
+        // The owner may re-prove; another wallet may not until the epoch rolls.
+        assert!(claim_nullifier(&mut nullifier, alice, 7, 254).is_ok());
+        assert_eq!(
+            claim_nullifier(&mut nullifier, bob, 7, 254),
+            Err(ZkLocationError::NullifierAlreadyUsed.into())
+        );
+        assert_eq!(nullifier.owner, alice);
+
+        assert!(claim_nullifier(&mut nullifier, bob, 8, 254).is_ok());
+        assert_eq!((nullifier.owner, nullifier.epoch), (bob, 8));
+    }
+
+    #[test]
+    fn fresh_nullifier_is_claimable_in_epoch_zero() {
+        let mut nullifier = record();
+        assert!(claim_nullifier(&mut nullifier, Pubkey::new_unique(), 0, 1).is_ok());
+    }
 }
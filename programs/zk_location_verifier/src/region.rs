@@ -0,0 +1,113 @@
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::keccak;
+
+// Domain tags keep ids of different geofence shapes from colliding.
+pub const REGION_DOMAIN_BBOX: &[u8] = b"radius:region:bbox:v1";
+pub const REGION_DOMAIN_CIRCLE: &[u8] = b"radius:region:circle:v1";
+pub const REGION_DOMAIN_POLYGON: &[u8] = b"radius:region:polygon:v1";
+pub const WALLET_DOMAIN: &[u8] = b"radius:wallet:v1";
+
+// Coordinates enter every circuit as offset micro-degrees, `lat * 1e6 + 90e6` and
+// `lon * 1e6 + 180e6`, so the southern and western hemispheres stay small
+// non-negative field elements (see circuits/coords.circom).
+pub const LAT_OFFSET: i64 = 90_000_000;
+pub const LON_OFFSET: i64 = 180_000_000;
+pub const MAX_LAT_INPUT: u64 = 2 * LAT_OFFSET as u64;
+pub const MAX_LON_INPUT: u64 = 2 * LON_OFFSET as u64;
+
+/// Canonical id of a bounding-box region.
+///
+/// `keccak256(REGION_DOMAIN_BBOX || min_lat || max_lat || min_lon || max_lon)`, where
+/// each bound is the 32-byte big-endian field element the prover passes as a public
+/// input ([`lat_input`] / [`lon_input`]). Market creators and provers must derive ids with this
+/// function (or its off-chain mirror in `lib/zkLocationClient.ts`).
+pub fn region_id(
+    min_lat: &[u8; 32],
+    max_lat: &[u8; 32],
+    min_lon: &[u8; 32],
+    max_lon: &[u8; 32],
+) -> [u8; 32] {
+    keccak::hashv(&[REGION_DOMAIN_BBOX, min_lat, max_lat, min_lon, max_lon]).to_bytes()
+}
+
+/// Canonical id of a circular region:
+/// `keccak256(REGION_DOMAIN_CIRCLE || target_lat || target_lon || radius_sq)`, limbs
+/// encoded as for [`region_id`]: the centre via [`lat_input`] / [`lon_input`], and
+/// `radius_sq` in squared micro-degrees, which the offsets cancel out of.
+pub fn radius_region_id(
+    target_lat: &[u8; 32],
+    target_lon: &[u8; 32],
+    radius_sq: &[u8; 32],
+) -> [u8; 32] {
+    keccak::hashv(&[REGION_DOMAIN_CIRCLE, target_lat, target_lon, radius_sq]).to_bytes()
+}
+
+/// Canonical id of a polygon region: `keccak256(REGION_DOMAIN_POLYGON || commitment)`,
+/// where `commitment` is the circuit's Poseidon chain over the padded vertex list
+/// (offset-encoded like every other coordinate).
+pub fn polygon_region_id(commitment: &[u8; 32]) -> [u8; 32] {
+    keccak::hashv(&[REGION_DOMAIN_POLYGON, commitment]).to_bytes()
+}
+
+/// Public input for a latitude in micro-degrees, offset by [`LAT_OFFSET`]. `None`
+/// outside ±90°. Mirrors `scaleLat` in `lib/zkProver.js`.
+pub fn lat_input(micro_deg: i64) -> Option<[u8; 32]> {
+    coord_input(micro_deg.checked_add(LAT_OFFSET)?, MAX_LAT_INPUT)
+}
+
+/// Public input for a longitude in micro-degrees, offset by [`LON_OFFSET`]. `None`
+/// outside ±180°. Mirrors `scaleLon` in `lib/zkProver.js`.
+pub fn lon_input(micro_deg: i64) -> Option<[u8; 32]> {
+    coord_input(micro_deg.checked_add(LON_OFFSET)?, MAX_LON_INPUT)
+}
+
+fn coord_input(offset: i64, max: u64) -> Option<[u8; 32]> {
+    let value = u64::try_from(offset).ok().filter(|v| *v <= max)?;
+    let mut limb = [0u8; 32];
+    limb[24..].copy_from_slice(&value.to_be_bytes());
+    Some(limb)
+}
+
+/// Public input binding a location proof to the wallet that submits it:
+/// `keccak256(WALLET_DOMAIN || wallet)` with the top byte cleared so it fits the
+/// BN254 scalar field. Mirrored by `walletInput` in `lib/zkLocationClient.ts`.
+pub fn wallet_input(wallet: &Pubkey) -> [u8; 32] {
+    let mut input = keccak::hashv(&[WALLET_DOMAIN, wallet.as_ref()]).to_bytes();
+    input[0] = 0;
+    input
+}
+
+/// Numeric value of a coordinate limb, or `None` if it does not fit a `u64` (a
+/// negative coordinate passed without the offset arrives as `p - x`).
+fn coord_value(limb: &[u8; 32]) -> Option<u64> {
+    let (high, low) = limb.split_at(24);
+    if high.iter().any(|b| *b != 0) {
+        return None;
+    }
+    Some(u64::from_be_bytes(low.try_into().ok()?))
+}
+
+/// Bounds `[min_lat, max_lat, min_lon, max_lon]` decoded from their limbs, or `None`
+/// unless they are in range for the offset encoding and not inverted.
+pub fn bbox_values(bounds: &[[u8; 32]; 4]) -> Option<[u64; 4]> {
+    let mut values = [0u64; 4];
+    for (value, limb) in values.iter_mut().zip(bounds) {
+        *value = coord_value(limb)?;
+    }
+    let [min_lat, max_lat, min_lon, max_lon] = values;
+    (min_lat <= max_lat
+        && max_lat <= MAX_LAT_INPUT
+        && min_lon <= max_lon
+        && max_lon <= MAX_LON_INPUT)
+        .then_some(values)
+}
+
+/// Whether the inner box lies inside (or on the edge of) the outer box. Bounds are
+/// compared as decoded offset coordinates; a box that fails [`bbox_values`]
+/// contains nothing and is contained by nothing.
+pub fn bbox_contains(outer: &[[u8; 32]; 4], inner: &[[u8; 32]; 4]) -> bool {
+    match (bbox_values(outer), bbox_values(inner)) {
+        (Some(o), Some(i)) => o[0] <= i[0] && i[1] <= o[1] && o[2] <= i[2] && i[3] <= o[3],
+        _ => false,
+    }
+}
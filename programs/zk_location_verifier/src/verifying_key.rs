@@ -1,35 +0,0 @@
-use groth16_solana::groth16::Groth16Verifyingkey;
-
-// Auto-generated from circuits/verification_key.json
-// Verifying key for spatial_check circuit
-pub fn verifying_key<'a>() -> Groth16Verifyingkey<'a> {
-    // Alpha G1 - [x, y]
-    const ALPHA_G1: [u8; 64] = [0x2d, 0x4d, 0x9a, 0xa7, 0xe3, 0x02, 0xd9, 0xdf, 0x41, 0x74, 0x9d, 0x55, 0x07, 0x94, 0x9d, 0x05, 0xdb, 0xea, 0x33, 0xfb, 0xb1, 0x6c, 0x64, 0x3b, 0x22, 0xf5, 0x99, 0xa2, 0xbe, 0x6d, 0xf2, 0xe2, 0x14, 0xbe, 0xdd, 0x50, 0x3c, 0x37, 0xce, 0xb0, 0x61, 0xd8, 0xec, 0x60, 0x20, 0x9f, 0xe3, 0x45, 0xce, 0x89, 0x83, 0x0a, 0x19, 0x23, 0x03, 0x01, 0xf0, 0x76, 0xca, 0xff, 0x00, 0x4d, 0x19, 0x26];
-
-    // Beta G2 - [x1, x0, y1, y0]
-    const BETA_G2: [u8; 128] = [0x09, 0x67, 0x03, 0x2f, 0xcb, 0xf7, 0x76, 0xd1, 0xaf, 0xc9, 0x85, 0xf8, 0x88, 0x77, 0xf1, 0x82, 0xd3, 0x84, 0x80, 0xa6, 0x53, 0xf2, 0xde, 0xca, 0xa9, 0x79, 0x4c, 0xbc, 0x3b, 0xf3, 0x06, 0x0c, 0x0e, 0x18, 0x78, 0x47, 0xad, 0x4c, 0x79, 0x83, 0x74, 0xd0, 0xd6, 0x73, 0x2b, 0xf5, 0x01, 0x84, 0x7d, 0xd6, 0x8b, 0xc0, 0xe0, 0x71, 0x24, 0x1e, 0x02, 0x13, 0xbc, 0x7f, 0xc1, 0x3d, 0xb7, 0xab, 0x30, 0x4c, 0xfb, 0xd1, 0xe0, 0x8a, 0x70, 0x4a, 0x99, 0xf5, 0xe8, 0x47, 0xd9, 0x3f, 0x8c, 0x3c, 0xaa, 0xfd, 0xde, 0xc4, 0x6b, 0x7a, 0x0d, 0x37, 0x9d, 0xa6, 0x9a, 0x4d, 0x11, 0x23, 0x46, 0xa7, 0x17, 0x39, 0xc1, 0xb1, 0xa4, 0x57, 0xa8, 0xc7, 0x31, 0x31, 0x23, 0xd2, 0x4d, 0x2f, 0x91, 0x92, 0xf8, 0x96, 0xb7, 0xc6, 0x3e, 0xea, 0x05, 0xa9, 0xd5, 0x7f, 0x06, 0x54, 0x7a, 0xd0, 0xce, 0xc8];
-
-    // Gamma G2 - [x1, x0, y1, y0]
-    const GAMMA_G2: [u8; 128] = [0x19, 0x8e, 0x93, 0x93, 0x92, 0x0d, 0x48, 0x3a, 0x72, 0x60, 0xbf, 0xb7, 0x31, 0xfb, 0x5d, 0x25, 0xf1, 0xaa, 0x49, 0x33, 0x35, 0xa9, 0xe7, 0x12, 0x97, 0xe4, 0x85, 0xb7, 0xae, 0xf3, 0x12, 0xc2, 0x18, 0x00, 0xde, 0xef, 0x12, 0x1f, 0x1e, 0x76, 0x42, 0x6a, 0x00, 0x66, 0x5e, 0x5c, 0x44, 0x79, 0x67, 0x43, 0x22, 0xd4, 0xf7, 0x5e, 0xda, 0xdd, 0x46, 0xde, 0xbd, 0x5c, 0xd9, 0x92, 0xf6, 0xed, 0x09, 0x06, 0x89, 0xd0, 0x58, 0x5f, 0xf0, 0x75, 0xec, 0x9e, 0x99, 0xad, 0x69, 0x0c, 0x33, 0x95, 0xbc, 0x4b, 0x31, 0x33, 0x70, 0xb3, 0x8e, 0xf3, 0x55, 0xac, 0xda, 0xdc, 0xd1, 0x22, 0x97, 0x5b, 0x12, 0xc8, 0x5e, 0xa5, 0xdb, 0x8c, 0x6d, 0xeb, 0x4a, 0xab, 0x71, 0x80, 0x8d, 0xcb, 0x40, 0x8f, 0xe3, 0xd1, 0xe7, 0x69, 0x0c, 0x43, 0xd3, 0x7b, 0x4c, 0xe6, 0xcc, 0x01, 0x66, 0xfa, 0x7d, 0xaa];
-
-    // Delta G2 - [x1, x0, y1, y0]
-    const DELTA_G2: [u8; 128] = [0x1b, 0xdb, 0x44, 0x3c, 0xe6, 0x1e, 0xbc, 0xf9, 0x3d, 0xae, 0xd4, 0x21, 0x5a, 0x57, 0xe1, 0xce, 0xc9, 0x0c, 0xa6, 0x49, 0x73, 0x02, 0x3e, 0x31, 0x19, 0x7a, 0x11, 0x2e, 0x8c, 0xee, 0x72, 0xf6, 0x11, 0x7e, 0x26, 0xc4, 0x23, 0x5f, 0x42, 0x62, 0x5b, 0x34, 0x04, 0xf2, 0x97, 0x30, 0x59, 0x81, 0x4f, 0xf2, 0x60, 0xdb, 0xa7, 0x17, 0x6d, 0x7a, 0xae, 0x91, 0x0f, 0x49, 0x3a, 0x8e, 0x1f, 0xb5, 0x19, 0x6e, 0x24, 0x7a, 0x12, 0xf5, 0x80, 0xe3, 0x68, 0x94, 0xf2, 0x56, 0xf0, 0xc1, 0x22, 0x39, 0x34, 0x89, 0xb4, 0xc2, 0x13, 0xb3, 0xe9, 0x13, 0x25, 0x0f, 0xee, 0xe8, 0x06, 0xca, 0x45, 0xff, 0x2f, 0xed, 0xff, 0x4e, 0x4b, 0xbc, 0xbf, 0x9f, 0x1c, 0x56, 0xe2, 0x8a, 0x06, 0x0d, 0x50, 0xee, 0xf5, 0xf5, 0x4e, 0x2a, 0xab, 0x89, 0x7f, 0x6b, 0x46, 0x5a, 0xcd, 0x2e, 0xd0, 0x05, 0xae, 0x91];
-
-    // IC (public input commitments) - 5 points
-    const IC: [[u8; 64]; 5] = [
-        [0x2e, 0x2a, 0x54, 0x28, 0x98, 0x76, 0x0d, 0xe5, 0x1b, 0xf2, 0x49, 0x3a, 0x7e, 0xd3, 0xd3, 0x65, 0x20, 0xa5, 0x7a, 0xe6, 0xc0, 0xb7, 0x48, 0x66, 0xea, 0xa2, 0xc6, 0x91, 0x7c, 0x17, 0xb2, 0xbc, 0x23, 0x25, 0x29, 0xfb, 0x16, 0xdd, 0x7b, 0x98, 0xba, 0x19, 0xbb, 0x3e, 0xfd, 0x65, 0x8c, 0x7b, 0xdb, 0x4b, 0x82, 0x8b, 0x07, 0x9a, 0x47, 0xf5, 0xa7, 0xac, 0xca, 0xdb, 0x27, 0x40, 0x23, 0x6e],
-        [0x20, 0xbe, 0x79, 0x44, 0x7a, 0xb9, 0x75, 0xb6, 0xa3, 0x7f, 0x4f, 0xfd, 0x76, 0x98, 0xc5, 0x07, 0x3d, 0xf0, 0x41, 0x85, 0xa2, 0x44, 0xbe, 0x64, 0xd6, 0xc3, 0xce, 0xc1, 0xcd, 0x40, 0x02, 0x1a, 0x1a, 0x21, 0xdc, 0x1d, 0x47, 0xad, 0x90, 0x6f, 0x66, 0x49, 0x0d, 0xa4, 0xce, 0x10, 0x33, 0xf3, 0x86, 0xeb, 0xbc, 0x57, 0x75, 0xee, 0x40, 0xaf, 0x19, 0x9e, 0x13, 0x9b, 0x34, 0x31, 0x2d, 0x24],
-        [0x19, 0xe0, 0x1a, 0x1b, 0x40, 0xf3, 0xdb, 0x04, 0xa2, 0x14, 0x84, 0x9a, 0x9e, 0x9f, 0x85, 0x2d, 0x6e, 0x88, 0x8e, 0xbe, 0xba, 0xda, 0xe2, 0x94, 0x61, 0x34, 0xe7, 0xc0, 0x71, 0x5f, 0x81, 0xc5, 0x19, 0x17, 0x1f, 0x87, 0x64, 0x2d, 0x83, 0xdc, 0x12, 0x16, 0xc1, 0x57, 0x1b, 0x75, 0x72, 0xd6, 0xee, 0xec, 0x75, 0xc8, 0x59, 0x7c, 0x4d, 0x79, 0xfd, 0x0a, 0xff, 0x1f, 0x6d, 0x9b, 0x81, 0x88],
-        [0x02, 0xb4, 0x02, 0x92, 0xd8, 0x25, 0x12, 0xed, 0x70, 0x0b, 0x5a, 0x27, 0x6d, 0xf3, 0x60, 0x23, 0xe5, 0xf3, 0x88, 0x3b, 0x54, 0x05, 0x18, 0x1c, 0x13, 0xe1, 0xe5, 0x63, 0x31, 0x95, 0xd1, 0x2b, 0x1c, 0x8f, 0x43, 0xa2, 0xd4, 0xcd, 0x2e, 0xa9, 0x6a, 0xe3, 0xec, 0x06, 0xd1, 0xf8, 0x00, 0xc9, 0x23, 0xa8, 0xec, 0xcf, 0x55, 0x9e, 0x2a, 0x2d, 0x96, 0x56, 0x67, 0x80, 0x27, 0x14, 0x40, 0x88],
-        [0x1a, 0xc0, 0x88, 0xd8, 0xc9, 0x8e, 0xd3, 0xaf, 0x33, 0xb2, 0xe0, 0x84, 0x0e, 0x70, 0x89, 0x67, 0x64, 0xed, 0x12, 0x01, 0xae, 0x9a, 0xcd, 0x5d, 0xf5, 0x4a, 0x48, 0xcf, 0x96, 0x1f, 0x47, 0xb7, 0x16, 0x90, 0x2e, 0xa7, 0x27, 0x3f, 0x33, 0x85, 0xcf, 0x61, 0x5a, 0x7f, 0x50, 0x07, 0x63, 0x18, 0x2c, 0xb6, 0x95, 0xd1, 0x11, 0x8b, 0xb5, 0x89, 0x4e, 0x59, 0xb5, 0xa8, 0x81, 0x2d, 0x42, 0xba],
-    ];
-
-    Groth16Verifyingkey {
-        nr_pubinputs: 4,
-        vk_alpha_g1: ALPHA_G1,
-        vk_beta_g2: BETA_G2,
-        vk_gamme_g2: GAMMA_G2,
-        vk_delta_g2: DELTA_G2,
-        vk_ic: &IC,
-    }
-}
\ No newline at end of file
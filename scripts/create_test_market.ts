@@ -22,6 +22,33 @@ const KEYPAIR_PATH = process.env.RESOLVER_KEYPAIR ??
 // Your USDC mint
 const USDC_MINT = new PublicKey("Gh9ZwEmdLJ8DscKNTkTqPbNwLNNBjuSzaG9Vp2KGtKJr");
 
+const BPF_UPGRADEABLE_LOADER = new PublicKey("BPFLoaderUpgradeab1e11111111111111111111111");
+
+// create_market needs the ProtocolConfig and DisputeConfig, which a fresh
+// deploy lacks. Create any that are missing (no protocol fee, no dispute
+// window, the wallet as admin/treasury/arbiter); the wallet must be the
+// program's upgrade authority.
+async function ensureConfigs(program: anchor.Program, admin: PublicKey) {
+  const connection = program.provider.connection;
+  const [protocolConfig] = PublicKey.findProgramAddressSync([Buffer.from("protocol-config")], PROGRAM_ID);
+  const [disputeConfig] = PublicKey.findProgramAddressSync([Buffer.from("dispute-config")], PROGRAM_ID);
+  if (!(await connection.getAccountInfo(protocolConfig))) {
+    const [programData] = PublicKey.findProgramAddressSync([PROGRAM_ID.toBuffer()], BPF_UPGRADEABLE_LOADER);
+    await program.methods
+      .initProtocolConfig(0, admin)
+      .accounts({ admin, protocolConfig, program: PROGRAM_ID, programData, systemProgram: anchor.web3.SystemProgram.programId })
+      .rpc();
+    console.log("   Initialized ProtocolConfig");
+  }
+  if (!(await connection.getAccountInfo(disputeConfig))) {
+    await program.methods
+      .setDisputeConfig(admin, new anchor.BN(0), new anchor.BN(0))
+      .accounts({ admin, protocolConfig, disputeConfig, systemProgram: anchor.web3.SystemProgram.programId })
+      .rpc();
+    console.log("   Initialized DisputeConfig");
+  }
+}
+
 function loadKeypair(filePath: string): Keypair {
   const raw = JSON.parse(fs.readFileSync(filePath, "utf8"));
   return Keypair.fromSecretKey(Uint8Array.from(raw));
@@ -37,9 +64,9 @@ function regionIdFromName(name: string): number[] {
   return Array.from(Buffer.from(hash, "hex"));
 }
 
-function hashManifest(manifest: object): number[] {
-  const json = JSON.stringify(manifest);
-  const hash = keccak256(json);
+// Hash the raw file bytes: resolvers must present the same hash on-chain.
+function hashManifest(raw: string): number[] {
+  const hash = keccak256(raw);
   return Array.from(Buffer.from(hash, "hex"));
 }
 
@@ -50,9 +77,12 @@ async function main() {
   const provider = new anchor.AnchorProvider(connection, wallet, { commitment: "confirmed" });
   const program = new anchor.Program(hyperlocalIdl, provider);
 
+  await ensureConfigs(program, keypair.publicKey);
+
   // Load manifest
   const manifestPath = path.resolve(process.cwd(), "manifests/example_weather.json");
-  const manifest = JSON.parse(fs.readFileSync(manifestPath, "utf8"));
+  const manifestRaw = fs.readFileSync(manifestPath, "utf8");
+  const manifest = JSON.parse(manifestRaw);
   
   const question = manifest.title;
   const regionName = "UMich"; // Use one of your regions
@@ -62,7 +92,7 @@ async function main() {
   
   const regionId = regionIdFromName(regionName);
   const qHash = questionHash(question);
-  const manifestHash = hashManifest(manifest);
+  const manifestHash = hashManifest(manifestRaw);
 
   // Derive PDAs
   const [marketPda] = PublicKey.findProgramAddressSync(
@@ -70,10 +100,10 @@ async function main() {
     PROGRAM_ID
   );
   
-  const [vaultPda] = PublicKey.findProgramAddressSync(
-    [Buffer.from("vault"), marketPda.toBuffer()],
-    PROGRAM_ID
-  );
+  const vaultPda = await anchor.utils.token.associatedAddress({
+    mint: USDC_MINT,
+    owner: marketPda,
+  });
 
   console.log("📊 Creating test market...");
   console.log("  Question:", question);
@@ -89,7 +119,12 @@ async function main() {
         question,
         new anchor.BN(closeTime),
         manifestPath, // URL or local path
-        manifestHash
+        manifestHash,
+        new anchor.BN(0), // max_proof_age_slots: no freshness limit for testing
+        0, // creator_fee_bps
+        2, // outcome_count: YES/NO
+        new anchor.BN(0), // initial_liquidity: none, start from 1-share priors
+        0 // initial_probability_bps
       )
       .accounts({
         payer: keypair.publicKey,
@@ -99,6 +134,7 @@ async function main() {
         resolver: keypair.publicKey, // You are the resolver for testing
         systemProgram: anchor.web3.SystemProgram.programId,
         tokenProgram: anchor.utils.token.TOKEN_PROGRAM_ID,
+        associatedTokenProgram: anchor.utils.token.ASSOCIATED_PROGRAM_ID,
         rent: anchor.web3.SYSVAR_RENT_PUBKEY,
       })
       .signers([keypair])
@@ -32,6 +32,33 @@ const USDC_MINT = new PublicKey("4zMMC9srt5Ri5X14GAgXhaHii3GnPAEERYPJgZJDncDU");
 const hyperlocalIdlPath = path.resolve(process.cwd(), "target/idl/hyperlocal_markets.json");
 const hyperlocalIdl = JSON.parse(fs.readFileSync(hyperlocalIdlPath, "utf8")) as anchor.Idl;
 
+const BPF_UPGRADEABLE_LOADER = new PublicKey("BPFLoaderUpgradeab1e11111111111111111111111");
+
+// create_market needs the ProtocolConfig and DisputeConfig, which a fresh
+// deploy lacks. Create any that are missing (no protocol fee, no dispute
+// window, the wallet as admin/treasury/arbiter); the wallet must be the
+// program's upgrade authority.
+async function ensureConfigs(program: anchor.Program, admin: PublicKey) {
+  const connection = program.provider.connection;
+  const [protocolConfig] = PublicKey.findProgramAddressSync([Buffer.from("protocol-config")], PROGRAM_ID);
+  const [disputeConfig] = PublicKey.findProgramAddressSync([Buffer.from("dispute-config")], PROGRAM_ID);
+  if (!(await connection.getAccountInfo(protocolConfig))) {
+    const [programData] = PublicKey.findProgramAddressSync([PROGRAM_ID.toBuffer()], BPF_UPGRADEABLE_LOADER);
+    await program.methods
+      .initProtocolConfig(0, admin)
+      .accounts({ admin, protocolConfig, program: PROGRAM_ID, programData, systemProgram: SystemProgram.programId })
+      .rpc();
+    console.log("   Initialized ProtocolConfig");
+  }
+  if (!(await connection.getAccountInfo(disputeConfig))) {
+    await program.methods
+      .setDisputeConfig(admin, new anchor.BN(0), new anchor.BN(0))
+      .accounts({ admin, protocolConfig, disputeConfig, systemProgram: SystemProgram.programId })
+      .rpc();
+    console.log("   Initialized DisputeConfig");
+  }
+}
+
 function loadKeypair(filePath: string): Keypair {
   const raw = JSON.parse(fs.readFileSync(filePath, "utf8"));
   return Keypair.fromSecretKey(Uint8Array.from(raw));
@@ -51,6 +78,8 @@ async function main() {
   console.log(`   Wallet: ${resolverKp.publicKey.toBase58()}`);
   console.log(`   Program: ${PROGRAM_ID.toBase58()}`);
 
+  await ensureConfigs(program, resolverKp.publicKey);
+
   // 2. PREPARE DATA
   const question = `Will it rain in smoke-test-city on ${Date.now()}?`;
   const regionId = new Uint8Array(32); // 32-byte generic region ID
@@ -99,14 +128,19 @@ async function main() {
   console.log("\n1️⃣  Creating Market...");
 
   try {
-    // Rust Signature: (region_id, question, close_time, manifest_url, manifest_hash)
+    // Rust Signature: (region_id, question, close_time, manifest_url, manifest_hash, max_proof_age_slots, creator_fee_bps, outcome_count, initial_liquidity, initial_probability_bps)
     const tx = await program.methods
       .createMarket(
         Array.from(regionId),   // region_id (array of numbers)
         question,        // question
         closeTime,       // close_time
         manifestUrl,     // manifest_url
-        Array.from(manifestHash)// manifest_hash
+        Array.from(manifestHash),// manifest_hash
+        new anchor.BN(0), // max_proof_age_slots (no limit)
+        0,                // creator_fee_bps
+        2,                // outcome_count (YES/NO)
+        new anchor.BN(0), // initial_liquidity (1-share priors)
+        0                 // initial_probability_bps
       )
       .accounts({
         payer: resolverKp.publicKey,
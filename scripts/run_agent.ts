@@ -12,6 +12,8 @@ import {
   ComputeBudgetProgram 
 } from "@solana/web3.js";
 import * as anchor from "@coral-xyz/anchor";
+// @ts-ignore - no types available
+import { keccak_256 } from "js-sha3";
 import {
   ResolutionManifest,
   OracleResult,
@@ -41,14 +43,54 @@ const GEMINI_API_URL = "https://generativelanguage.googleapis.com/v1beta/models/
 const OUTCOME_NONE = 0;
 const OUTCOME_YES = 1;
 const OUTCOME_NO = 2;
-const STATUS_RESOLVED = 2; // Usually 2 in Rust enums (Active=1, Resolved=2, disputed=3 etc. verify your Rust state)
+const OUTCOME_INVALID = 255; // Void: refunds every position
 
 interface MarketAccount {
   manifestUrl: string;
   manifestHash: number[];
-  status: number;
+  status: Record<string, object>; // Anchor enum, e.g. { open: {} }
   closeTime: number | anchor.BN;
   question: string;
+  proposedAt: anchor.BN;
+  disputeWindowSecs: anchor.BN;
+  evidenceCount: number;
+  resolver: PublicKey;
+  minResolverStake: anchor.BN;
+}
+
+// On-chain limits (hyperlocal_markets MAX_URL_LEN / MAX_REASON_LEN, in bytes).
+const MAX_URL_LEN = 256;
+const MAX_REASON_LEN = 256;
+
+function truncateBytes(text: string, max: number): string {
+  const bytes = Buffer.from(text, "utf8");
+  return bytes.length <= max ? text : bytes.subarray(0, max).toString("utf8").replace(/\uFFFD$/, "");
+}
+
+// Evidence argument for agent_attempt_resolution, plus its EvidenceRecord PDA.
+function evidenceFor(market: PublicKey, attempt: number, result: OracleResult) {
+  const url = result.evidenceUrl || "";
+  let domain = "";
+  try {
+    domain = new URL(url).hostname;
+  } catch {
+    // Not a URL (e.g. empty); the domain hash covers the empty string.
+  }
+  const attemptLe = Buffer.alloc(4);
+  attemptLe.writeUInt32LE(attempt);
+  const [record] = PublicKey.findProgramAddressSync(
+    [Buffer.from("evidence"), market.toBuffer(), attemptLe],
+    PROGRAM_ID
+  );
+  return {
+    evidence: {
+      url: truncateBytes(url, MAX_URL_LEN),
+      contentHash: keccak_256.array(result.evidenceContent || ""),
+      fetchedAt: new anchor.BN(Math.floor(Date.now() / 1000)),
+      sourceDomainHash: keccak_256.array(domain),
+    },
+    record,
+  };
 }
 
 // 2. HELPER FUNCTIONS
@@ -57,11 +99,45 @@ function loadKeypair(filePath: string): Keypair {
   return Keypair.fromSecretKey(Uint8Array.from(raw));
 }
 
-async function fetchManifest(market: MarketAccount): Promise<ResolutionManifest | null> {
+interface LoadedManifest {
+  manifest: ResolutionManifest;
+  // keccak256 of the raw manifest bytes; must equal the market's manifest_hash.
+  hash: number[];
+}
+
+function loadedManifest(raw: string, manifest: ResolutionManifest): LoadedManifest {
+  return { manifest, hash: keccak_256.array(raw) };
+}
+
+// Sealed ["manifest", market] store, if the creator uploaded the manifest on-chain.
+async function fetchOnChainManifest(program: anchor.Program, market: PublicKey): Promise<string | null> {
+  const [storePda] = PublicKey.findProgramAddressSync(
+    [Buffer.from("manifest"), market.toBuffer()],
+    PROGRAM_ID
+  );
+  const store = await (program.account as any).manifestStore.fetchNullable(storePda);
+  if (!store || !store.sealed) return null;
+  return Buffer.from(store.data).toString("utf8");
+}
+
+async function fetchManifest(
+  program: anchor.Program,
+  marketKey: PublicKey,
+  market: MarketAccount
+): Promise<LoadedManifest | null> {
   const url = market.manifestUrl;
-  if (!url) return null;
 
   try {
+    const onChain = await fetchOnChainManifest(program, marketKey);
+    if (onChain) {
+      const parsed = JSON.parse(onChain);
+      if (isValidManifest(parsed)) {
+        console.log("  ✓ Loaded sealed on-chain manifest");
+        return loadedManifest(onChain, parsed);
+      }
+    }
+    if (!url) return null;
+
     // First, try to parse as inline JSON (auto-generated manifests)
     if (url.startsWith("{")) {
       const parsed = JSON.parse(url);
@@ -69,12 +145,12 @@ async function fetchManifest(market: MarketAccount): Promise<ResolutionManifest
       // Check for compact manifest format first
       if (isCompactManifest(parsed)) {
         console.log("  ✓ Parsed compact inline manifest, expanding...");
-        return expandCompactManifest(parsed);
+        return loadedManifest(url, expandCompactManifest(parsed));
       }
       
       if (isValidManifest(parsed)) {
         console.log("  ✓ Parsed inline manifest JSON");
-        return parsed;
+        return loadedManifest(url, parsed);
       }
     }
 
@@ -82,8 +158,9 @@ async function fetchManifest(market: MarketAccount): Promise<ResolutionManifest
     if (url.startsWith("http")) {
       const response = await fetch(url);
       if (!response.ok) throw new Error(`HTTP error ${response.status}`);
-      const data = await response.json();
-      if (isValidManifest(data)) return data;
+      const raw = await response.text();
+      const data = JSON.parse(raw);
+      if (isValidManifest(data)) return loadedManifest(raw, data);
     } else {
       // Try as file path
       let filePath = url;
@@ -103,7 +180,7 @@ async function fetchManifest(market: MarketAccount): Promise<ResolutionManifest
       if (fs.existsSync(filePath)) {
         const raw = fs.readFileSync(filePath, "utf8");
         const parsed = JSON.parse(raw);
-        if (isValidManifest(parsed)) return parsed;
+        if (isValidManifest(parsed)) return loadedManifest(raw, parsed);
       }
     }
   } catch (e) {
@@ -224,12 +301,13 @@ export async function resolveMarketLogic(manifest: ResolutionManifest): Promise<
     1. FILTERING: Search results often contain "noise" (e.g., if the question is about Football, ignore results about Basketball or Baseball).
     2. DATES: Ensure the evidence matches the specific year/date in the Question.
     3. SPECIFICITY: If 9 results discuss unrelated topics and 1 result contains the exact answer, rely on that 1 result.
+    4. INVALID: Only if credible sources confirm the event was cancelled or the question cannot have a YES/NO answer, return INVALID. Missing evidence is UNSURE, not INVALID.
 
     EVIDENCE:
     ${evidenceText}
 
     RESPONSE FORMAT:
-    Return valid JSON only: { "outcome": "YES"|"NO"|"UNSURE", "confidence": number (0.0-1.0), "reason": "concise explanation citing the specific source URL used" }`;
+    Return valid JSON only: { "outcome": "YES"|"NO"|"INVALID"|"UNSURE", "confidence": number (0.0-1.0), "reason": "concise explanation citing the specific source URL used" }`;
 
     const geminiResponse = await callGemini(prompt);
     
@@ -250,22 +328,23 @@ export async function resolveMarketLogic(manifest: ResolutionManifest): Promise<
        }
     }
 
-    const outcome = ["YES", "NO", "UNSURE"].includes(parsed.outcome) ? parsed.outcome : "UNSURE";
+    const outcome = ["YES", "NO", "INVALID", "UNSURE"].includes(parsed.outcome) ? parsed.outcome : "UNSURE";
     
     // LOGIC TO FIND WHICH URL WAS USED
     // If the AI cites a specific source in the 'reason', try to find that URL. 
     // Otherwise default to the first one, but this is less accurate.
-    let evidenceUrl = results[0]?.url || "";
+    let evidence = results[0];
     if (parsed.reason) {
       const match = results.find(r => parsed.reason.includes(r.url) || r.snippet.includes(parsed.reason.substring(0, 20)));
-      if (match) evidenceUrl = match.url;
+      if (match) evidence = match;
     }
 
     return {
       outcome: outcome as OracleResult["outcome"],
       confidence: parsed.confidence || 0,
       reason: parsed.reason || "AI Logic",
-      evidenceUrl: evidenceUrl,
+      evidenceUrl: evidence?.url || "",
+      evidenceContent: evidence?.snippet || "",
     };
   } catch (e) {
     console.error("Gemini Logic Error:", e);
@@ -282,14 +361,39 @@ async function runOnce(program: anchor.Program, resolverKp: Keypair): Promise<nu
   const markets = await (program.account["market"] as any).all() as { publicKey: PublicKey; account: MarketAccount }[];
   
   const nowTs = Math.floor(Date.now() / 1000);
+
+  // Finalize our earlier proposals whose dispute window passed unchallenged.
+  for (const { publicKey, account } of markets) {
+    if (!("proposed" in account.status)) continue;
+    if (account.proposedAt.toNumber() + account.disputeWindowSecs.toNumber() > nowTs) continue;
+    // Staked markets release the proposal from the resolver's stake lock.
+    const [resolverStake] = PublicKey.findProgramAddressSync(
+      [Buffer.from("resolver-stake"), account.resolver.toBuffer()],
+      PROGRAM_ID
+    );
+    try {
+      const tx = await program.methods
+        .finalizeResolution()
+        .accounts({
+          market: publicKey,
+          resolverStake: account.minResolverStake.isZero() ? null : resolverStake,
+        })
+        .rpc();
+      console.log(`\n🏁 Finalized ${publicKey.toBase58().slice(0, 8)}... Tx: https://explorer.solana.com/tx/${tx}?cluster=devnet`);
+    } catch (e) {
+      console.error(`  ❌ Finalize failed:`, e);
+    }
+  }
+
   const candidates = markets.filter((m) => {
-    const status = m.account.status as number;
     const closeTime = typeof m.account.closeTime === 'number' 
       ? m.account.closeTime 
       : m.account.closeTime.toNumber();
       
-    // Must be NOT resolved AND time must be in the past
-    return status !== STATUS_RESOLVED && closeTime < nowTs;
+    // Must be Open (or Disputed without a challenge) AND time must be in the past.
+    // Proposed and Finalized markets are handled above / done.
+    const status = m.account.status;
+    return ("open" in status || "disputed" in status) && closeTime < nowTs;
   });
 
   if (candidates.length === 0) {
@@ -302,13 +406,18 @@ async function runOnce(program: anchor.Program, resolverKp: Keypair): Promise<nu
   for (const { publicKey, account } of candidates) {
     console.log(`\n🔍 Processing: ${publicKey.toBase58().slice(0, 8)}... | "${account.question}"`);
     
-    const manifest = await fetchManifest(account);
-    if (!manifest) {
+    const loaded = await fetchManifest(program, publicKey, account);
+    if (!loaded) {
       console.warn(`  ⚠️ Skipping - Manifest unreadable.`);
       continue;
     }
+    // The program rejects resolutions made against a different manifest.
+    if (Buffer.compare(Buffer.from(loaded.hash), Buffer.from(account.manifestHash)) !== 0) {
+      console.warn(`  ⚠️ Skipping - Manifest does not match the market's manifest_hash.`);
+      continue;
+    }
 
-    const result = await resolveMarketLogic(manifest);
+    const result = await resolveMarketLogic(loaded.manifest);
     console.log(`  🤖 Verdict: ${result.outcome} (confidence: ${(result.confidence * 100).toFixed(0)}%)`);
     console.log(`  📝 Reason: ${result.reason}`);
 
@@ -317,19 +426,39 @@ async function runOnce(program: anchor.Program, resolverKp: Keypair): Promise<nu
       continue;
     }
 
-    const outcomeU8 = result.outcome === "YES" ? OUTCOME_YES : OUTCOME_NO;
+    const outcomeU8 =
+      result.outcome === "YES" ? OUTCOME_YES
+      : result.outcome === "NO" ? OUTCOME_NO
+      : OUTCOME_INVALID;
+    // Markets that require a staked resolver check our registry entry.
+    const [resolverStake] = PublicKey.findProgramAddressSync(
+      [Buffer.from("resolver-stake"), resolverKp.publicKey.toBuffer()],
+      PROGRAM_ID
+    );
+    const staked = (await program.provider.connection.getAccountInfo(resolverStake)) !== null;
+    const { evidence, record } = evidenceFor(publicKey, account.evidenceCount, result);
     const modifyComputeUnits = ComputeBudgetProgram.setComputeUnitLimit({ units: 200_000 });
     const addPriorityFee = ComputeBudgetProgram.setComputeUnitPrice({ microLamports: 100_000 });
 
     try {
       const tx = await program.methods
-        .agentAttemptResolution(outcomeU8, result.evidenceUrl || "", result.reason)
-        .accounts({ market: publicKey, resolver: resolverKp.publicKey })
+        .agentAttemptResolution(
+          outcomeU8,
+          evidence,
+          truncateBytes(result.reason, MAX_REASON_LEN),
+          loaded.hash
+        )
+        .accounts({
+          market: publicKey,
+          resolver: resolverKp.publicKey,
+          resolverStake: staked ? resolverStake : null,
+          evidenceRecord: record,
+        })
         .preInstructions([modifyComputeUnits, addPriorityFee])
         .signers([resolverKp])
         .rpc();
 
-      console.log(`  ✅ Resolved! Tx: https://explorer.solana.com/tx/${tx}?cluster=devnet`);
+      console.log(`  ✅ Proposed! Tx: https://explorer.solana.com/tx/${tx}?cluster=devnet`);
       resolved++;
     } catch (e) {
       console.error(`  ❌ Transaction failed:`, e);
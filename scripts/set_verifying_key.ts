@@ -0,0 +1,149 @@
+/**
+ * Upload a circuit verifying key into zk_location_verifier.
+ * spatial_check (default): creates the Config PDA on first run (init_config), upgrades a legacy
+ * bump-only Config (migrate_config), rotates afterwards (set_verifying_key). Creating or migrating
+ * the Config must be signed by the program's upgrade authority, which becomes its admin.
+ * radius_check / polygon_check: write the ["vk", kind] slot via set_circuit_verifying_key.
+ * Run with: npx ts-node --project tsconfig.scripts.json scripts/set_verifying_key.ts
+ */
+import fs from "fs";
+import path from "path";
+import "dotenv/config";
+import { Connection, Keypair, PublicKey } from "@solana/web3.js";
+import * as anchor from "@coral-xyz/anchor";
+
+const zkIdlPath = path.resolve(process.cwd(), "target/idl/zk_location_verifier.json");
+const zkIdl = JSON.parse(fs.readFileSync(zkIdlPath, "utf8")) as anchor.Idl;
+
+const PROGRAM_ID = new PublicKey(
+  process.env.ZK_LOCATION_PROGRAM_ID ?? "56qEvUYQnhfEf557ftLVdjtwqfJA6TpG2cnru3MyhT7Z"
+);
+const RPC_URL = process.env.RPC_URL ?? "https://api.devnet.solana.com";
+const KEYPAIR_PATH = process.env.ADMIN_KEYPAIR ??
+  path.join(process.env.HOME || ".", ".config", "solana", "id.json");
+const BPF_UPGRADEABLE_LOADER = new PublicKey("BPFLoaderUpgradeab1e11111111111111111111111");
+// Discriminator plus the lone bump byte of a Config created before keys were stored on-chain.
+const LEGACY_CONFIG_LEN = 8 + 1;
+const CIRCUIT = process.env.CIRCUIT ?? "spatial_check";
+const VK_PATH = process.env.VK_PATH ??
+  path.resolve(
+    process.cwd(),
+    CIRCUIT === "spatial_check"
+      ? "circuits/verification_key.json"
+      : `circuits/${CIRCUIT}_verification_key.json`
+  );
+
+// Circuits with their own ["vk", seed] slot (see zk_location_verifier::CircuitKind).
+const CIRCUIT_SLOTS: Record<string, { kind: object; seed: string }> = {
+  radius_check: { kind: { radius: {} }, seed: "radius" },
+  polygon_check: { kind: { polygon: {} }, seed: "polygon" },
+};
+
+function loadKeypair(filePath: string): Keypair {
+  const raw = JSON.parse(fs.readFileSync(filePath, "utf8"));
+  return Keypair.fromSecretKey(Uint8Array.from(raw));
+}
+
+// groth16-solana expects big-endian 32-byte limbs.
+function toBytes32(dec: string): number[] {
+  let hex = BigInt(dec).toString(16);
+  if (hex.length % 2) hex = "0" + hex;
+  const bytes = hex.match(/.{1,2}/g)?.map((b) => parseInt(b, 16)) || [];
+  return Array(32 - bytes.length).fill(0).concat(bytes);
+}
+
+function g1(point: string[]): number[] {
+  return [...toBytes32(point[0]), ...toBytes32(point[1])];
+}
+
+// G2 coordinates are stored as [x1, x0, y1, y0].
+function g2(point: string[][]): number[] {
+  return [
+    ...toBytes32(point[0][1]),
+    ...toBytes32(point[0][0]),
+    ...toBytes32(point[1][1]),
+    ...toBytes32(point[1][0]),
+  ];
+}
+
+function storedVerifyingKey(vk: any) {
+  const ic = vk.IC ?? vk.ic;
+  if (!ic) {
+    throw new Error("verification_key.json missing IC array");
+  }
+  return {
+    nrPubinputs: vk.nPublic,
+    alphaG1: g1(vk.vk_alpha_1),
+    betaG2: g2(vk.vk_beta_2),
+    gammaG2: g2(vk.vk_gamma_2),
+    deltaG2: g2(vk.vk_delta_2),
+    ic: ic.map((p: string[]) => g1(p)),
+  };
+}
+
+async function main() {
+  const keypair = loadKeypair(KEYPAIR_PATH);
+  const connection = new Connection(RPC_URL, "confirmed");
+  const wallet = new anchor.Wallet(keypair);
+  const provider = new anchor.AnchorProvider(connection, wallet, { commitment: "confirmed" });
+  const program = new anchor.Program(zkIdl, provider);
+
+  const vk = storedVerifyingKey(JSON.parse(fs.readFileSync(VK_PATH, "utf8")));
+  const [configPda] = PublicKey.findProgramAddressSync([Buffer.from("config")], PROGRAM_ID);
+
+  console.log("🔑 Uploading verifying key...");
+  console.log("  Circuit:", CIRCUIT);
+
+  const slot = CIRCUIT_SLOTS[CIRCUIT];
+  if (slot) {
+    const [vkSlotPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("vk"), Buffer.from(slot.seed)],
+      PROGRAM_ID
+    );
+    const tx = await program.methods
+      .setCircuitVerifyingKey(slot.kind, vk)
+      .accounts({
+        admin: keypair.publicKey,
+        config: configPda,
+        vkSlot: vkSlotPda,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .rpc();
+    const stored: any = await (program.account as any).circuitVerifyingKey.fetch(vkSlotPda);
+    console.log("✅ Verifying key stored (version", stored.vkVersion, ")");
+    console.log("  Tx:", `https://explorer.solana.com/tx/${tx}?cluster=devnet`);
+    return;
+  }
+
+  const existing = await connection.getAccountInfo(configPda);
+
+  console.log("  Config PDA:", configPda.toBase58());
+  console.log("  Public inputs:", vk.nrPubinputs);
+
+  const [programData] = PublicKey.findProgramAddressSync(
+    [PROGRAM_ID.toBuffer()],
+    BPF_UPGRADEABLE_LOADER
+  );
+  const upgradeAccounts = {
+    payer: keypair.publicKey,
+    config: configPda,
+    program: PROGRAM_ID,
+    programData,
+    systemProgram: anchor.web3.SystemProgram.programId,
+  };
+
+  const tx = !existing
+    ? await program.methods.initConfig(vk).accounts(upgradeAccounts).rpc()
+    : existing.data.length === LEGACY_CONFIG_LEN
+    ? await program.methods.migrateConfig(vk).accounts(upgradeAccounts).rpc()
+    : await program.methods
+        .setVerifyingKey(vk)
+        .accounts({ admin: keypair.publicKey, config: configPda })
+        .rpc();
+
+  const config: any = await (program.account as any).config.fetch(configPda);
+  console.log("✅ Verifying key stored (version", config.vkVersion, ")");
+  console.log("  Tx:", `https://explorer.solana.com/tx/${tx}?cluster=devnet`);
+}
+
+main().catch(console.error);